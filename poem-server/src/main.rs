@@ -65,7 +65,11 @@ T+uzn0sroycBiBfVB949LExnzGDFUkhG0i2c2InarQYLTsIyHCIDEA==
 
 #[handler]
 fn log(req: Json<serde_json::Value>) -> Json<serde_json::Value> {
-    println!("{:?}", req.0);
+    // rejected screenshots get their own line so its easy to grep for why a file was skipped
+    match (req.0.get("fileKey"), req.0.get("reason")) {
+        (Some(file_key), Some(reason)) => println!("REJECTED {file_key}: {reason}"),
+        _ => println!("{:?}", req.0),
+    }
     Json(serde_json::Value::Null)
 }
 
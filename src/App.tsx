@@ -42,6 +42,10 @@ function sendLog(log: any, alsoConsoleLog?: boolean) {
   })
 }
 
+type Outcome = 'win' | 'loss' | 'draw' | 'unknown';
+
+type Scene = 'end_screen' | 'shop' | 'battle' | 'main_menu' | 'unrelated';
+
 type SAPScreenshot = {
   fileKey: string;
   numHearts: number;
@@ -49,8 +53,204 @@ type SAPScreenshot = {
   hasBandage: boolean;
   // set to true for files that arent actually a SAP screenshot
   invalid?: boolean;
+  // why the file was rejected, if it is invalid
+  invalidReason?: string;
+  // which screen of the game the file looks like. only logged, nothing is skipped on it
+  // until the scene signatures are measured. missing for screenshots cached before scenes were classified
+  scene?: Scene;
+  sceneConfidence?: number;
+  // how sure we are of the turn count. see TURN_REVIEW_CONFIDENCE
+  turnConfidence?: number;
+  // confidence of each turn digit, left to right.
+  // missing for screenshots cached before it was recorded per digit
+  turnDigitConfidence?: number[];
+  // missing for screenshots cached before outcomes were detected
+  outcome?: Outcome;
+  // level (1-3) and filled experience pips of each team pet, null if unknown
+  teamLevel?: (number | null)[];
+  teamExperience?: (number | null)[];
+  // hearts of lives lost in earlier turns, and of the life lost this turn.
+  // missing for screenshots cached before hearts were told apart
+  emptyHearts?: number;
+  lostHearts?: number;
+  // icons drawn over the heart slots (slot counts from 0, left to right). name is null
+  // if the decoration is not in the names wasm knows about.
+  // missing for screenshots cached before decorations were detected
+  heartDecorations?: HeartDecoration[];
+};
+
+type HeartDecoration = { slot: number; name: string | null; confidence: number };
+
+// field offsets of the record returned by wasm_extract. see wasm/src/record.rs
+const RECORD_FORMAT_VERSION = 1;
+const TEAM_SIZE = 5;
+const MAX_DECORATIONS = 4;
+const MAX_TURN_DIGITS = 3;
+const RECORD_FIELD = {
+  version: 0,
+  status: 1,
+  numHearts: 2,
+  hasBandage: 3,
+  turnNumber: 4,
+  turnConfidence: 5,
+  outcome: 6,
+  teamLevel: 7,
+  teamExperience: 7 + TEAM_SIZE,
+  emptyHearts: 7 + TEAM_SIZE * 2,
+  lostHearts: 8 + TEAM_SIZE * 2,
+  heartDecorationSlots: 9 + TEAM_SIZE * 2,
+  heartDecorationIds: 9 + TEAM_SIZE * 2 + MAX_DECORATIONS,
+  heartDecorationConfidence: 9 + TEAM_SIZE * 2 + MAX_DECORATIONS * 2,
+  turnDigitConfidence: 9 + TEAM_SIZE * 2 + MAX_DECORATIONS * 3,
+  scene: 9 + TEAM_SIZE * 2 + MAX_DECORATIONS * 3 + MAX_TURN_DIGITS,
+  sceneConfidence: 10 + TEAM_SIZE * 2 + MAX_DECORATIONS * 3 + MAX_TURN_DIGITS,
+};
+
+// Outcome codes, see wasm/src/outcome.rs
+const OUTCOMES: { [code: number]: Outcome } = {
+  0: 'unknown',
+  1: 'win',
+  2: 'loss',
+  3: 'draw',
 };
 
+// Scene codes, see wasm/src/scene.rs
+const SCENES: { [code: number]: Scene } = {
+  0: 'unrelated',
+  1: 'end_screen',
+  2: 'shop',
+  3: 'battle',
+  4: 'main_menu',
+};
+
+// screenshots used to be assumed to be wins, so those cached before outcomes
+// existed, and those whose banner could not be classified (see needsOutcomeReview),
+// still count as one
+function isWin(s: SAPScreenshot): boolean {
+  if (s.invalid) { return false }
+  return s.outcome !== 'loss' && s.outcome !== 'draw';
+}
+
+// extracted, but the banner does not tell how the game ended
+function needsOutcomeReview(s: SAPScreenshot): boolean {
+  if (s.invalid) { return false }
+  return s.outcome === 'unknown';
+}
+
+// turn numbers whose least certain digit is below this confidence
+// (in thousandths, see DigitReading in wasm/src/lib.rs) get flagged for review
+const TURN_REVIEW_CONFIDENCE = 1000;
+
+// ExtractError codes, see wasm/src/error.rs
+const EXTRACT_ERRORS: { [code: number]: string } = {
+  1: 'decode: the file is not an image we can decode',
+  2: 'wrong_dimensions: not a landscape screenshot',
+  3: 'missing_margin: could not find the game viewport between the margins',
+  5: 'digit_segmentation: could not split the turn counter into digits',
+};
+
+function extractErrorReason(code: number): string {
+  return EXTRACT_ERRORS[code] ?? `unknown error code ${code}`;
+}
+
+type ExtractionRecord = {
+  status: number;
+  numHearts: number;
+  hasBandage: boolean;
+  turnCount: number;
+  turnConfidence: number;
+  turnDigitConfidence: number[];
+  outcome: Outcome;
+  teamLevel: (number | null)[];
+  teamExperience: (number | null)[];
+  emptyHearts: number;
+  lostHearts: number;
+  heartDecorations: HeartDecoration[];
+  scene: Scene;
+  // in thousandths
+  sceneConfidence: number;
+};
+
+// TEAM_SIZE words starting at `start`, with -1 (unreadable) turned into null
+function teamStatWords(words: Int32Array, start: number): (number | null)[] {
+  return Array.from(words.slice(start, start + TEAM_SIZE)).map((v) => v < 0 ? null : v);
+}
+
+// the [pointer, length] wasm wrote to `outPtr`. it comes from alloc, so it may not be 4 byte aligned
+function readOut(exports: any, outPtr: number): [number, number] {
+  const view = new DataView(exports.memory.buffer, outPtr, 8);
+  return [view.getUint32(0, true), view.getUint32(4, true)];
+}
+
+// reads a newline separated list of names from one of the wasm_*_names exports
+function readNames(exports: any, namesExport: string): string[] {
+  const outPtr = exports.alloc(8);
+  exports[namesExport](outPtr);
+  const [namesPtr, namesLen] = readOut(exports, outPtr);
+  const text = new TextDecoder().decode(new Uint8Array(exports.memory.buffer, namesPtr, namesLen));
+  exports.dealloc(namesPtr, namesLen);
+  exports.dealloc(outPtr, 8);
+  return text.length === 0 ? [] : text.split('\n');
+}
+
+// names of the heart slot decorations. decoration id n is decorationNames[n - 1]
+let decorationNames: string[] | null = null;
+
+function getDecorationNames(exports: any): string[] {
+  if (decorationNames === null) {
+    decorationNames = readNames(exports, 'wasm_heart_decoration_names');
+  }
+  return decorationNames;
+}
+
+function heartDecorationWords(exports: any, words: Int32Array): HeartDecoration[] {
+  const decorations: HeartDecoration[] = [];
+  for (let i = 0; i < MAX_DECORATIONS; i += 1) {
+    const slot = words[RECORD_FIELD.heartDecorationSlots + i];
+    if (slot < 0) { continue }
+    const id = words[RECORD_FIELD.heartDecorationIds + i];
+    const name = getDecorationNames(exports)[id - 1] ?? null;
+    decorations.push({ slot, name, confidence: words[RECORD_FIELD.heartDecorationConfidence + i] });
+  }
+  return decorations;
+}
+
+function extractScreenshot(wasm: WebAssembly.WebAssemblyInstantiatedSource, bytes: Uint8Array): ExtractionRecord {
+  const exports: any = wasm.instance.exports;
+  const ptr = exports.alloc(bytes.length);
+  // copy the content of the file into the memory buffer
+  new Uint8Array(exports.memory.buffer, ptr, bytes.length).set(bytes);
+  // wasm writes the [pointer, length] of the result record here
+  const outPtr = exports.alloc(8);
+  exports.wasm_extract(ptr, bytes.length, outPtr);
+  const [recordPtr, recordLen] = readOut(exports, outPtr);
+  // copy the words out before freeing the record
+  const words = new Int32Array(exports.memory.buffer.slice(recordPtr, recordPtr + recordLen));
+  exports.dealloc(recordPtr, recordLen);
+  exports.dealloc(outPtr, 8);
+
+  if (words[RECORD_FIELD.version] !== RECORD_FORMAT_VERSION) {
+    throw new Error(`unsupported extraction record version ${words[RECORD_FIELD.version]}`);
+  }
+  return {
+    status: words[RECORD_FIELD.status],
+    numHearts: words[RECORD_FIELD.numHearts],
+    hasBandage: words[RECORD_FIELD.hasBandage] !== 0,
+    turnCount: words[RECORD_FIELD.turnNumber],
+    turnConfidence: words[RECORD_FIELD.turnConfidence],
+    turnDigitConfidence: Array.from(words.slice(RECORD_FIELD.turnDigitConfidence, RECORD_FIELD.turnDigitConfidence + MAX_TURN_DIGITS))
+      .filter((c) => c >= 0),
+    outcome: OUTCOMES[words[RECORD_FIELD.outcome]] ?? 'unknown',
+    teamLevel: Array.from(words.slice(RECORD_FIELD.teamLevel, RECORD_FIELD.teamLevel + TEAM_SIZE)).map((v) => v === 0 ? null : v),
+    teamExperience: teamStatWords(words, RECORD_FIELD.teamExperience),
+    emptyHearts: words[RECORD_FIELD.emptyHearts],
+    lostHearts: words[RECORD_FIELD.lostHearts],
+    heartDecorations: heartDecorationWords(exports, words),
+    scene: SCENES[words[RECORD_FIELD.scene]] ?? 'unrelated',
+    sceneConfidence: words[RECORD_FIELD.sceneConfidence],
+  };
+}
+
 function getCachedData(): { [key: string]: SAPScreenshot } {
   const screenshotmap = window.localStorage.getItem('sapscreenshots')
   if (!screenshotmap) { return {} }
@@ -159,51 +359,24 @@ function App() {
           const file = await getFile(dirHandle, fileKey);
           sendLog(`got file file ${file.name}. size: ${file.size}`);
           const ab = await file.arrayBuffer();
-          // @ts-ignore
-          const ptr = wasm.instance.exports.alloc(file.size);
-          // @ts-ignore
-          var mem = new Uint8Array(wasm.instance.exports.memory.buffer, ptr, file.size);
-          // copy the content of the file into the memory buffer
-          const abWindow = new Uint8Array(ab);
-          mem.set(abWindow);
-
-          // @ts-ignore
-          let res: BigInt = BigInt(wasm.instance.exports.wasm_entrypoint(ptr, file.size));
-          if (res === -1n) {
-            sendLog(`not a pets screenshot: ${fileKey}`);
-            appendCachedData({ fileKey, numHearts: 0, hasBandage: false, invalid: true, turnCount: 0 });
+          const bytes = new Uint8Array(ab);
+          const record = extractScreenshot(wasm, bytes);
+          if (record.status !== 0) {
+            const invalidReason = extractErrorReason(record.status);
+            sendLog({ msg: 'rejected screenshot', fileKey, code: record.status, reason: invalidReason });
+            appendCachedData({ fileKey, numHearts: 0, hasBandage: false, invalid: true, invalidReason, turnCount: 0 });
             continue;
           }
 
-          const mask = BigInt(1 << 3);
-          // @ts-ignore
-          let numHearts = res & 15n; // get only the 4 LSB bits
-          let hasBandage = false;
-          // @ts-ignore
-          if ((res & mask) != 0) {
-            // has bandage, need to clear the bit
-            numHearts = numHearts &= ~mask;
-            hasBandage = true;
-          }
-          // @ts-ignore
-          let turnCount = res >> 4n;
-          let base = 10n;
-          // @ts-ignore
-          if ((turnCount & 16n) != 0) {
-            base = 20n;
-            turnCount -= 10n;
-          }
-          //                 remove the 0b10000 bit
-          // @ts-ignore
-          turnCount = base + (turnCount & 15n);
-          const sapscreenshot: SAPScreenshot = { fileKey, numHearts: Number(numHearts), hasBandage, turnCount: Number(turnCount) };
+          const { scene, sceneConfidence, numHearts, hasBandage, turnCount, turnConfidence, turnDigitConfidence, outcome, teamLevel, teamExperience, emptyHearts, lostHearts, heartDecorations } = record;
+          const sapscreenshot: SAPScreenshot = { fileKey, scene, sceneConfidence, numHearts, hasBandage, turnCount, turnConfidence, turnDigitConfidence, outcome, teamLevel, teamExperience, emptyHearts, lostHearts, heartDecorations };
           setScreenshots((prev) => {
             const newPrev = [...prev];
             newPrev.push(sapscreenshot);
             return newPrev;
           });
           appendCachedData(sapscreenshot);
-          sendLog(`${fileKey} : numHearts=${numHearts}, hasBandage=${hasBandage}`);
+          sendLog(`${fileKey} : scene=${scene}, sceneConfidence=${sceneConfidence}, numHearts=${numHearts}, hasBandage=${hasBandage}, turnCount=${turnCount}, turnConfidence=${turnConfidence}, turnDigitConfidence=${turnDigitConfidence.join(',')}, outcome=${outcome}, teamLevel=${teamLevel.join(',')}, teamExperience=${teamExperience.join(',')}, emptyHearts=${emptyHearts}, lostHearts=${lostHearts}, heartDecorations=${heartDecorations.map((d) => `${d.slot}:${d.name}@${d.confidence}`).join(',')}`);
         }
         sendLog(`got all ${fileKeys.length} files`);
 
@@ -244,14 +417,21 @@ function App() {
   }, [wasm, setWasm, setDebugWasmResult]);
 
   const totalWins = useMemo(() => {
-    return screenshots.filter((s) => !s.invalid).length
+    return screenshots.filter(isWin).length
   }, [screenshots]);
+  const totalLosses = useMemo(() => screenshots.filter(s => !s.invalid && s.outcome === 'loss').length, [screenshots]);
+  const totalDraws = useMemo(() => screenshots.filter(s => !s.invalid && s.outcome === 'draw').length, [screenshots]);
+  const totalUnknownOutcomes = useMemo(() => screenshots.filter(needsOutcomeReview).length, [screenshots]);
+  const winRate = useMemo(() => {
+    const games = totalWins + totalLosses + totalDraws;
+    return games === 0 ? 0 : totalWins / games;
+  }, [totalWins, totalLosses, totalDraws]);
 
   const dateChartWins = useMemo(() => {
     const dateMap: { [key: string]: SAPScreenshot[] } = {};
     for (let i = 0; i < screenshots.length; i += 1) {
       const s = screenshots[i];
-      if (s.invalid) { continue }
+      if (!isWin(s)) { continue }
       const date = getDate(s.fileKey);
       const dateStr = formatDate(date);
       if (dateMap.hasOwnProperty(dateStr)) {
@@ -343,13 +523,13 @@ function App() {
   };
 
   const dayStats = useMemo(() => {
-    const monday = screenshots.filter(s => !s.invalid).map(s => getDay(0, s.fileKey)).filter(Boolean).length;
-    const tuesday = screenshots.filter(s => !s.invalid).map(s => getDay(1, s.fileKey)).filter(Boolean).length;
-    const wednesday = screenshots.filter(s => !s.invalid).map(s => getDay(2, s.fileKey)).filter(Boolean).length;
-    const thursday = screenshots.filter(s => !s.invalid).map(s => getDay(3, s.fileKey)).filter(Boolean).length;
-    const friday = screenshots.filter(s => !s.invalid).map(s => getDay(4, s.fileKey)).filter(Boolean).length;
-    const saturday = screenshots.filter(s => !s.invalid).map(s => getDay(5, s.fileKey)).filter(Boolean).length;
-    const sunday = screenshots.filter(s => !s.invalid).map(s => getDay(6, s.fileKey)).filter(Boolean).length;
+    const monday = screenshots.filter(isWin).map(s => getDay(0, s.fileKey)).filter(Boolean).length;
+    const tuesday = screenshots.filter(isWin).map(s => getDay(1, s.fileKey)).filter(Boolean).length;
+    const wednesday = screenshots.filter(isWin).map(s => getDay(2, s.fileKey)).filter(Boolean).length;
+    const thursday = screenshots.filter(isWin).map(s => getDay(3, s.fileKey)).filter(Boolean).length;
+    const friday = screenshots.filter(isWin).map(s => getDay(4, s.fileKey)).filter(Boolean).length;
+    const saturday = screenshots.filter(isWin).map(s => getDay(5, s.fileKey)).filter(Boolean).length;
+    const sunday = screenshots.filter(isWin).map(s => getDay(6, s.fileKey)).filter(Boolean).length;
     return {
       monday,
       tuesday,
@@ -365,7 +545,7 @@ function App() {
     const turnCountMap: { [key: number]: number } = {};
     for (let i = 0; i < screenshots.length; i += 1) {
       const screenshot = screenshots[i];
-      if (screenshot.invalid) { continue }
+      if (!isWin(screenshot)) { continue }
       const turnCount = screenshot.turnCount;
       if (turnCountMap.hasOwnProperty(turnCount)) {
         turnCountMap[turnCount] += 1;
@@ -382,7 +562,7 @@ function App() {
     })
   }, [screenshots]);
   const winsWithBandage = useMemo(() => {
-    const wins = screenshots.filter(x => !x.invalid);
+    const wins = screenshots.filter(isWin);
     let winsWithB = 0;
     for (let i = 0; i < wins.length; i += 1) {
       if (wins[i].hasBandage) {
@@ -409,6 +589,8 @@ function App() {
         pick directory
       </button>
       <h3>total wins: {totalWins}</h3>
+      <h4>losses: {totalLosses}, draws: {totalDraws}, win rate: {(winRate * 100).toFixed(1)}%</h4>
+      {totalUnknownOutcomes > 0 && <h4>counted as wins, outcome needs review: {totalUnknownOutcomes}</h4>}
       <h4>wins without bandage: {winsWithoutBandage}</h4>
       <h4>wins with bandage: {winsWithBandage}</h4>
       <ol>
@@ -450,9 +632,18 @@ function App() {
 
 function ScreenshotItem({ screenshot }: { screenshot: SAPScreenshot}) {
   const bandageText = screenshot.hasBandage ? 'has bandage' : '';
+  const otherDecorations = (screenshot.heartDecorations ?? []).filter((d) => d.name !== 'bandage');
+  const decorationText = otherDecorations.map((d) => `${d.name ?? 'unknown icon'} on heart ${d.slot + 1}`).join(', ');
+  const lostText = screenshot.lostHearts ? `(lost ${screenshot.lostHearts} this turn)` : '';
+  const needsReview = screenshot.turnConfidence !== undefined && screenshot.turnConfidence < TURN_REVIEW_CONFIDENCE;
+  // which digits (counting from 1) are unsure, if we know them per digit
+  const unsureDigits = (screenshot.turnDigitConfidence ?? []).flatMap((c, i) => c < TURN_REVIEW_CONFIDENCE ? [i + 1] : []);
+  const turnReview = unsureDigits.length > 0 ? `(turn count needs review: digit ${unsureDigits.join(', ')})` : '(turn count needs review)';
+  const reviewText = needsReview ? turnReview : '';
+  const outcomeText = { win: 'won', loss: 'lost', draw: 'drew', unknown: 'ended (outcome needs review)' }[screenshot.outcome ?? 'win'];
   return (
     <li>
-      {screenshot.fileKey} won @turn={screenshot.turnCount}. has {screenshot.numHearts} {bandageText}
+      {screenshot.fileKey} {outcomeText} @turn={screenshot.turnCount}. has {screenshot.numHearts} {lostText} {bandageText} {decorationText} {reviewText}
     </li>
   )
 }
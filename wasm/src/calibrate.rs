@@ -0,0 +1,217 @@
+//! find the regions of interest in a screenshot from an unknown device or
+//! game version, so nobody has to measure pixel offsets in an image editor.
+//! give it one known-good win screenshot and it produces a `LayoutProfile`
+
+use image::{DynamicImage, GenericImageView, SubImage};
+
+use crate::*;
+use crate::components::{Component, Mask};
+
+/// smallest heart we accept, as a fraction of the viewport height.
+/// anything smaller is considered noise
+const MIN_HEART_HEIGHT: f64 = 0.02;
+
+/// the digits of the turn counter are at most this fraction of the viewport height
+const MAX_DIGIT_HEIGHT: f64 = 0.125;
+/// and at least this fraction
+const MIN_DIGIT_HEIGHT: f64 = 0.015;
+
+/// pixels at least this bright could be part of a turn counter digit. the exact
+/// split between digit and box is made later by `threshold::binarize`
+const MIN_DIGIT_LUMA: u8 = 200;
+
+/// search a known-good win screenshot for the row of hearts and the turn counter,
+/// and build a profile describing where they are. everything else is copied from `base`
+pub fn calibrate(img: &DynamicImage, base: &LayoutProfile) -> Result<LayoutProfile, String> {
+    let probpets = probable_pets_image_check(img).map_err(|e| e.to_string())?;
+    let view = &probpets.view;
+    let (width, height) = view.dimensions();
+
+    let hearts = find_hearts(view, &base.heart_colors).ok_or("could not find any hearts")?;
+    let first_heart = hearts[0];
+    let pad = first_heart.height() / 20;
+    let heart_view_height = first_heart.height() + pad * 2;
+    let heart_view_width = match hearts.windows(2).map(|pair| pair[1].min_x - pair[0].min_x).min() {
+        Some(heart_spacing) => heart_spacing * (base.max_hearts as u32).saturating_sub(1) + first_heart.width() + pad * 2,
+        // a single heart has no neighbour to measure the spacing to, keep the proportions of the base heart row
+        None => {
+            let aspect_ratio = (base.heart_view.width * width as f64) / (base.heart_view.height * height as f64);
+            (heart_view_height as f64 * aspect_ratio).round() as u32
+        }
+    };
+    let heart_view = (
+        first_heart.min_x.saturating_sub(pad),
+        first_heart.min_y.saturating_sub(pad),
+        heart_view_width,
+        heart_view_height,
+    );
+
+    let turn_number_view = find_turn_number_box(view).ok_or("could not find the turn number")?;
+
+    Ok(LayoutProfile {
+        name: "calibrated".to_string(),
+        heart_view: normalize_rect(heart_view, width, height),
+        turn_number_view: normalize_rect(turn_number_view, width, height),
+        ..base.clone()
+    })
+}
+
+pub fn calibrate_from_bytes(bytes: &[u8], base: &LayoutProfile) -> Result<LayoutProfile, String> {
+    let img = image::load_from_memory(bytes).map_err(|e| e.to_string())?;
+    calibrate(&img, base)
+}
+
+fn normalize_rect(rect: (u32, u32, u32, u32), width: u32, height: u32) -> NormRect {
+    let (x, y, w, h) = rect;
+    let w = w.min(width - x);
+    let h = h.min(height - y);
+    NormRect {
+        x: x as f64 / width as f64,
+        y: y as f64 / height as f64,
+        width: w as f64 / width as f64,
+        height: h as f64 / height as f64,
+    }
+}
+
+/// find the hearts by looking for blobs in any of the heart colors.
+/// the hearts are the biggest red blobs that sit on the same row.
+/// returns them sorted left to right
+fn find_hearts(view: &SubImage<&DynamicImage>, heart_colors: &[[u8; 4]]) -> Option<Vec<Component>> {
+    let (width, height) = view.dimensions();
+    let mask = Mask::new(width, height, |x, y| {
+        let px = view.get_pixel(x, y).0;
+        heart_colors.iter().any(|c| color::color_matches(px, *c, color::COLOR_TOLERANCE))
+    });
+    let components = mask.connected_components();
+    let largest = components.iter().max_by_key(|c| c.area)?;
+    if (largest.height() as f64) < height as f64 * MIN_HEART_HEIGHT {
+        return None;
+    }
+    let mut hearts: Vec<Component> = components.iter()
+        .filter(|c| c.area * 2 >= largest.area)
+        .filter(|c| c.min_y.abs_diff(largest.min_y) < largest.height() / 2)
+        .copied()
+        .collect();
+    hearts.sort_by_key(|c| c.min_x);
+    Some(hearts)
+}
+
+/// the turn counter is two (near) white digits of the same height next to each other.
+/// every such pair of white blobs is a candidate. each candidate box is run through
+/// the same digit clipping used to read the turn number, and the one whose digits
+/// look most like our digit templates wins
+fn find_turn_number_box(view: &SubImage<&DynamicImage>) -> Option<(u32, u32, u32, u32)> {
+    let (width, height) = view.dimensions();
+    let mask = Mask::new(width, height, |x, y| threshold::luma(view.get_pixel(x, y).0) >= MIN_DIGIT_LUMA);
+    let min_digit_height = (height as f64 * MIN_DIGIT_HEIGHT) as u32;
+    let max_digit_height = (height as f64 * MAX_DIGIT_HEIGHT) as u32;
+    let mut glyphs: Vec<Component> = mask.connected_components().into_iter()
+        .filter(|c| (min_digit_height..=max_digit_height).contains(&c.height()))
+        .filter(|c| c.width() <= c.height() * 3 / 2)
+        .collect();
+    glyphs.sort_by_key(|c| c.min_x);
+
+    let mut best: Option<((u32, u32, u32, u32), f64)> = None;
+    for (i, left) in glyphs.iter().enumerate() {
+        for right in glyphs[i + 1..].iter() {
+            if right.min_x <= left.max_x {
+                continue;
+            }
+            if right.min_x - left.max_x > left.height() {
+                break;
+            }
+            let similar_height = left.height().abs_diff(right.height()) * 4 <= left.height();
+            let aligned = left.min_y.abs_diff(right.min_y) * 5 <= left.height();
+            if !similar_height || !aligned {
+                continue;
+            }
+            let candidate = digit_pair_box(left, right, width, height);
+            let Some(distance) = digit_pair_distance(view, candidate) else {
+                continue;
+            };
+            if best.is_none_or(|(_, best_distance)| distance < best_distance) {
+                best = Some((candidate, distance));
+            }
+        }
+    }
+    best.map(|(rect, _)| rect)
+}
+
+/// the box around two digits, padded the same way the turn counter box is:
+/// the digit templates have one empty row above and below each digit
+fn digit_pair_box(left: &Component, right: &Component, width: u32, height: u32) -> (u32, u32, u32, u32) {
+    let top = left.min_y.min(right.min_y);
+    let bottom = left.max_y.max(right.max_y);
+    let glyph_height = bottom - top + 1;
+    let pad_y = (glyph_height / 14).max(1);
+    let pad_x = (glyph_height / 14).max(1);
+    let x = left.min_x.saturating_sub(pad_x);
+    let y = top.saturating_sub(pad_y);
+    let w = (right.max_x + 1 + pad_x).min(width) - x;
+    let h = (bottom + 1 + pad_y).min(height) - y;
+    (x, y, w, h)
+}
+
+fn digit_pair_distance(view: &SubImage<&DynamicImage>, rect: (u32, u32, u32, u32)) -> Option<f64> {
+    let candidate = view.view(rect.0, rect.1, rect.2, rect.3);
+    let digits = get_digits_from_view(&candidate)?;
+    let (left, right) = PetsImage::get_digits_vectors_from_two_dyn_images(digits);
+    let closest = |vector: &[u8; 256]| get_digit_similarity(vector).into_iter().fold(f64::MAX, f64::min);
+    Some(closest(&left) + closest(&right))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testutil::SynthScreenshot;
+
+    fn shifted_layout() -> LayoutProfile {
+        let base = LayoutProfile::default();
+        LayoutProfile {
+            name: "shifted".to_string(),
+            heart_view: NormRect { x: 0.13, y: 0.59, ..base.heart_view },
+            turn_number_view: NormRect { x: 0.55, y: 0.2, ..base.turn_number_view },
+            ..base
+        }
+    }
+
+    #[test]
+    fn can_calibrate_shifted_layout() {
+        let layout = shifted_layout();
+        let known_good = SynthScreenshot {
+            margin_left: 131,
+            num_hearts: 4,
+            heart_spacing: 0.085,
+            turn_number: 16,
+            layout: layout.clone(),
+            ..SynthScreenshot::new(2400, 1080)
+        }.render();
+        let calibrated = calibrate(&known_good, &LayoutProfile::default()).expect("it should calibrate");
+        assert!((calibrated.heart_view.x - layout.heart_view.x).abs() < 0.005);
+        assert!((calibrated.heart_view.y - layout.heart_view.y).abs() < 0.005);
+        assert!((calibrated.turn_number_view.x - layout.turn_number_view.x).abs() < 0.005);
+        assert!((calibrated.turn_number_view.y - layout.turn_number_view.y).abs() < 0.005);
+
+        // the calibrated profile should read other screenshots with the same layout
+        let other = SynthScreenshot {
+            margin_left: 131,
+            num_hearts: 2,
+            heart_spacing: 0.085,
+            has_bandage: true,
+            turn_number: 23,
+            layout,
+            ..SynthScreenshot::new(2400, 1080)
+        }.render();
+        let probpets = probable_pets_image_check(&other).expect("it should be a pets img");
+        let petsimg = PetsImage::new(&probpets, &calibrated);
+        assert_eq!(petsimg.heart_data.num_hearts, 2);
+        assert!(petsimg.heart_data.has_bandage());
+        assert_eq!(petsimg.get_turn_number().value(), Some(23));
+    }
+
+    #[test]
+    fn calibration_fails_without_hearts() {
+        let img = SynthScreenshot { num_hearts: 0, ..SynthScreenshot::new(2400, 1080) }.render();
+        assert!(calibrate(&img, &LayoutProfile::default()).is_err());
+    }
+}
@@ -0,0 +1,61 @@
+//! matching pixels against the flat colors the game UI is drawn in
+
+use image::{DynamicImage, GenericImageView, SubImage};
+
+/// how far off (per channel) a pixel may be from a UI color and still count as it.
+/// covers recompression and color management, not a different color
+pub const COLOR_TOLERANCE: u8 = 24;
+
+pub fn color_matches(px: [u8; 4], color: [u8; 4], tolerance: u8) -> bool {
+    px.iter().zip(color.iter()).take(3).all(|(a, b)| a.abs_diff(*b) <= tolerance)
+}
+
+/// fraction of the pixels of the view that are `color` (within `COLOR_TOLERANCE`)
+pub fn color_coverage(view: &SubImage<&DynamicImage>, color: [u8; 4]) -> f64 {
+    let (width, height) = view.dimensions();
+    let matching = view.pixels().filter(|(_, _, px)| color_matches(px.0, color, COLOR_TOLERANCE)).count();
+    matching as f64 / (width * height).max(1) as f64
+}
+
+/// a region is flat (has nothing drawn on it) if this fraction of it is the same color
+const FLAT_COVERAGE: f64 = 0.95;
+
+/// whether (almost) the whole view is a single color, give or take `COLOR_TOLERANCE`.
+/// the color is the per channel median, so a big icon cannot drag it away from the background
+pub fn is_flat(view: &SubImage<&DynamicImage>) -> bool {
+    let mut channels: [Vec<u8>; 3] = Default::default();
+    for (_, _, px) in view.pixels() {
+        for (c, values) in channels.iter_mut().enumerate() {
+            values.push(px.0[c]);
+        }
+    }
+    let median = channels.map(|mut values| {
+        values.sort_unstable();
+        values.get(values.len() / 2).copied().unwrap_or(0)
+    });
+    color_coverage(view, [median[0], median[1], median[2], 255]) >= FLAT_COVERAGE
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn coverage_tolerates_small_differences() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(10, 10, |x, _| {
+            image::Rgba(if x < 4 { [250, 190, 10, 255] } else { [40, 120, 200, 255] })
+        }));
+        assert_eq!(color_coverage(&img.view(0, 0, 10, 10), [255, 196, 0, 255]), 0.4);
+        assert_eq!(color_coverage(&img.view(0, 0, 10, 10), [0, 0, 0, 255]), 0.0);
+    }
+
+    #[test]
+    fn flat_means_a_single_color() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(10, 10, |x, y| {
+            image::Rgba(if x < 3 && y < 3 { [245, 245, 235, 255] } else { [230, 220, 200, 255] })
+        }));
+        assert!(!is_flat(&img.view(0, 0, 10, 10)));
+        assert!(is_flat(&img.view(4, 4, 6, 6)));
+    }
+}
@@ -0,0 +1,99 @@
+/// bounding box and size of a group of touching pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Component {
+    pub min_x: u32,
+    pub min_y: u32,
+    pub max_x: u32,
+    pub max_y: u32,
+    /// number of pixels in the component
+    pub area: u32,
+}
+
+impl Component {
+    pub fn width(&self) -> u32 {
+        self.max_x - self.min_x + 1
+    }
+    pub fn height(&self) -> u32 {
+        self.max_y - self.min_y + 1
+    }
+}
+
+const NEIGHBOURS: [(i32, i32); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
+/// a width x height grid of on/off pixels
+pub struct Mask {
+    pub width: u32,
+    pub height: u32,
+    pub bits: Vec<bool>,
+}
+
+impl Mask {
+    pub fn new(width: u32, height: u32, is_set: impl Fn(u32, u32) -> bool) -> Self {
+        let mut bits = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                bits.push(is_set(x, y));
+            }
+        }
+        Self { width, height, bits }
+    }
+
+    /// find every 8-connected group of set pixels, in the order their
+    /// top-most, left-most pixel appears in the mask
+    pub fn connected_components(&self) -> Vec<Component> {
+        let mut visited = vec![false; self.bits.len()];
+        let mut out = vec![];
+        let mut stack = vec![];
+        for start in 0..self.bits.len() {
+            if !self.bits[start] || visited[start] {
+                continue;
+            }
+            let (x, y) = (start as u32 % self.width, start as u32 / self.width);
+            let mut component = Component { min_x: x, min_y: y, max_x: x, max_y: y, area: 0 };
+            visited[start] = true;
+            stack.push((x, y));
+            while let Some((x, y)) = stack.pop() {
+                component.area += 1;
+                component.min_x = component.min_x.min(x);
+                component.min_y = component.min_y.min(y);
+                component.max_x = component.max_x.max(x);
+                component.max_y = component.max_y.max(y);
+                for (dx, dy) in NEIGHBOURS {
+                    let (nx, ny) = (x.wrapping_add_signed(dx), y.wrapping_add_signed(dy));
+                    if nx >= self.width || ny >= self.height {
+                        continue;
+                    }
+                    let i = (ny * self.width + nx) as usize;
+                    if self.bits[i] && !visited[i] {
+                        visited[i] = true;
+                        stack.push((nx, ny));
+                    }
+                }
+            }
+            out.push(component);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn finds_separate_components() {
+        // two blobs: a 2x2 square and a diagonally connected L shape
+        let rows = [
+            "##...",
+            "##..#",
+            "....#",
+            "...#.",
+        ];
+        let mask = Mask::new(5, 4, |x, y| rows[y as usize].as_bytes()[x as usize] == b'#');
+        let components = mask.connected_components();
+        assert_eq!(components, vec![
+            Component { min_x: 0, min_y: 0, max_x: 1, max_y: 1, area: 4 },
+            Component { min_x: 3, min_y: 1, max_x: 4, max_y: 3, area: 3 },
+        ]);
+    }
+}
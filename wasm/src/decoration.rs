@@ -0,0 +1,96 @@
+//! recognize the status icons drawn over the heart slots (eg: the bandage that
+//! protects a life). each heart slot is cropped to its heart and matched against
+//! reference crops of decorated and plain hearts, see `icon::classify_icon`
+
+use image::{DynamicImage, SubImage};
+
+use crate::icon::{classify_icon, icon_labels, icon_vector, IconVector};
+pub use crate::heart_decorations::HEART_DECORATIONS;
+
+pub const BANDAGE: &str = "bandage";
+
+/// the label of the references of hearts without anything drawn over them
+pub const PLAIN_HEART: &str = "plain";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeartDecoration {
+    /// index of the heart slot it is drawn over, left to right
+    pub slot: usize,
+    pub name: &'static str,
+    /// `IconMatch::confidence` of the match. 0 for a bandage found by the bandage
+    /// probe, which is only used while there are no `HEART_DECORATIONS`
+    pub confidence: f64,
+}
+
+impl HeartDecoration {
+    /// the id the decoration is sent across the wasm boundary as,
+    /// one more than its index in `decoration_names`
+    pub fn id(&self) -> i32 {
+        decoration_names().iter().position(|n| *n == self.name).map_or(0, |i| i as i32 + 1)
+    }
+}
+
+/// every decoration that can be reported. the bandage always comes first,
+/// since it can be found by the bandage probe even without references
+pub fn decoration_names() -> Vec<&'static str> {
+    let mut names = vec![BANDAGE];
+    names.extend(icon_labels(HEART_DECORATIONS).into_iter().filter(|n| *n != PLAIN_HEART && *n != BANDAGE));
+    names
+}
+
+/// match every heart slot against the embedded `HEART_DECORATIONS`
+pub fn detect_decorations(slots: &[SubImage<&DynamicImage>]) -> Vec<HeartDecoration> {
+    detect_decorations_with(slots, HEART_DECORATIONS)
+}
+
+/// same as `detect_decorations` but against any set of references. slots that
+/// look like a plain heart, or like nothing in the set, have no decoration
+pub fn detect_decorations_with(slots: &[SubImage<&DynamicImage>], references: &[(&'static str, IconVector)]) -> Vec<HeartDecoration> {
+    slots.iter().enumerate()
+        .filter_map(|(slot, view)| {
+            let found = classify_icon(&icon_vector(view), references)?;
+            (found.label != PLAIN_HEART).then_some(HeartDecoration { slot, name: found.label, confidence: found.confidence })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::{GenericImageView, RgbaImage};
+
+    const RED: [u8; 4] = [253, 6, 6, 255];
+
+    /// a heart slot with a square of `patch` drawn over its top left quarter
+    fn heart(size: u32, patch: Option<[u8; 4]>) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(size, size, |x, y| {
+            match patch {
+                Some(color) if x < size / 2 && y < size / 2 => image::Rgba(color),
+                _ => image::Rgba(RED),
+            }
+        }))
+    }
+
+    #[test]
+    fn finds_decorated_slots() {
+        let references = [
+            (PLAIN_HEART, icon_vector(&heart(40, None).view(0, 0, 40, 40))),
+            (BANDAGE, icon_vector(&heart(40, Some([240, 220, 200, 255])).view(0, 0, 40, 40))),
+        ];
+        let plain = heart(64, None);
+        let bandaged = heart(64, Some([236, 224, 196, 255]));
+        let other_icon = heart(64, Some([20, 200, 40, 255]));
+        let slots = [bandaged.view(0, 0, 64, 64), plain.view(0, 0, 64, 64), other_icon.view(0, 0, 64, 64)];
+        let found = detect_decorations_with(&slots, &references);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].slot, found[0].name), (0, BANDAGE));
+        assert!(found[0].confidence > 0.0);
+        assert_eq!(found[0].id(), 1);
+    }
+
+    #[test]
+    fn nothing_is_found_without_references() {
+        let bandaged = heart(64, Some([236, 224, 196, 255]));
+        assert_eq!(detect_decorations_with(&[bandaged.view(0, 0, 64, 64)], &[]), vec![]);
+    }
+}
@@ -0,0 +1,66 @@
+use std::fmt;
+
+/// why a screenshot could not be extracted. each variant is one stage of
+/// the pipeline and has a stable numeric code that is sent across the wasm
+/// boundary in the extraction record. codes are never reused or renumbered,
+/// 4 belonged to `no_red_heart` before screens without hearts became zero hearts,
+/// 6 to `not_the_game` while screens without hearts had to match a scene signature
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtractError {
+    /// the bytes are not an image we can decode
+    Decode(String),
+    /// the image is not landscape, so it cannot be a screenshot of the game
+    WrongDimensions { width: u32, height: u32 },
+    /// the solid black band on the left or right side is wider than a notch margin can be
+    /// (a quarter of the width), so we cannot tell where the game viewport starts
+    MissingMargin,
+    /// the turn counter could not be split into its digits
+    DigitSegmentation,
+}
+
+impl ExtractError {
+    pub fn code(&self) -> i32 {
+        match self {
+            ExtractError::Decode(_) => 1,
+            ExtractError::WrongDimensions { .. } => 2,
+            ExtractError::MissingMargin => 3,
+            ExtractError::DigitSegmentation => 5,
+        }
+    }
+}
+
+impl fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtractError::Decode(e) => write!(f, "failed to decode image: {e}"),
+            ExtractError::WrongDimensions { width, height } => write!(f, "{width}x{height} is not a landscape screenshot"),
+            ExtractError::MissingMargin => write!(f, "could not find the game viewport between the margins"),
+            ExtractError::DigitSegmentation => write!(f, "could not split the turn counter into digits"),
+        }
+    }
+}
+
+impl std::error::Error for ExtractError {}
+
+impl From<image::ImageError> for ExtractError {
+    fn from(e: image::ImageError) -> Self {
+        ExtractError::Decode(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn codes_are_stable() {
+        let errors = [
+            ExtractError::Decode("bad png".to_string()),
+            ExtractError::WrongDimensions { width: 1080, height: 2400 },
+            ExtractError::MissingMargin,
+            ExtractError::DigitSegmentation,
+        ];
+        let codes: Vec<i32> = errors.iter().map(|e| e.code()).collect();
+        assert_eq!(codes, vec![1, 2, 3, 5]);
+    }
+}
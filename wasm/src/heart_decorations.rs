@@ -0,0 +1,10 @@
+// @generated by template-gen from labelled heart slot crops. do not edit by hand,
+// see wasm/template-gen for how to regenerate it.
+//
+// scaled down rgb crop of each heart slot, labelled with the icon drawn over it
+// (or `plain`), see `icon::icon_vector`.
+
+use crate::icon::IconVector;
+
+pub const HEART_DECORATIONS: &[(&str, IconVector)] = &[
+];
@@ -0,0 +1,133 @@
+//! count the hearts (lives) in the heart row. the row is segmented by color:
+//! every pixel close enough to one of the colors a kind of heart is drawn in
+//! is set, and each group of touching pixels big enough to be a heart counts
+//! as one. nothing depends on where exactly the hearts are in the row, how far
+//! apart they are, how many there are, or on their exact color
+
+use image::{DynamicImage, GenericImageView, SubImage};
+
+use crate::color::{color_matches, COLOR_TOLERANCE};
+use crate::components::{Component, Mask};
+
+/// a heart is at least this fraction of the height of the heart row.
+/// anything smaller is a speck, or a bit of a heart colored decoration
+const MIN_HEART_HEIGHT: f64 = 0.4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeartKind {
+    Full,
+    Empty,
+    Lost,
+}
+
+/// how many hearts of each kind the heart row shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HeartCounts {
+    /// lives left
+    pub full: usize,
+    /// lives lost in earlier turns
+    pub empty: usize,
+    /// the life lost this turn, still drawn breaking
+    pub lost: usize,
+}
+
+impl HeartCounts {
+    pub fn total(&self) -> usize {
+        self.full + self.empty + self.lost
+    }
+}
+
+fn is_any_of(px: [u8; 4], colors: &[[u8; 4]]) -> bool {
+    colors.iter().any(|c| color_matches(px, *c, COLOR_TOLERANCE))
+}
+
+/// every heart drawn in one of `colors`, left to right
+pub fn find_hearts(view: &SubImage<&DynamicImage>, colors: &[[u8; 4]]) -> Vec<Component> {
+    let (width, height) = view.dimensions();
+    let mask = Mask::new(width, height, |x, y| is_any_of(view.get_pixel(x, y).0, colors));
+    let min_height = height as f64 * MIN_HEART_HEIGHT;
+    let mut hearts: Vec<Component> = mask.connected_components().into_iter()
+        .filter(|c| c.height() as f64 >= min_height)
+        .collect();
+    hearts.sort_by_key(|c| c.min_x);
+    hearts
+}
+
+/// every heart slot of the row, left to right. a game has at most `max_hearts`
+/// lives, so anything heart shaped to the right of that many hearts is not one
+pub fn find_heart_slots(view: &SubImage<&DynamicImage>, full: &[[u8; 4]], empty: &[[u8; 4]], lost: &[[u8; 4]], max_hearts: usize) -> Vec<(HeartKind, Component)> {
+    let mut hearts: Vec<(HeartKind, Component)> = [(HeartKind::Full, full), (HeartKind::Empty, empty), (HeartKind::Lost, lost)].into_iter()
+        .flat_map(|(kind, colors)| find_hearts(view, colors).into_iter().map(move |c| (kind, c)))
+        .collect();
+    hearts.sort_by_key(|(_, c)| c.min_x);
+    hearts.truncate(max_hearts);
+    hearts
+}
+
+/// count the hearts of each kind, see `find_heart_slots`
+pub fn count_hearts(view: &SubImage<&DynamicImage>, full: &[[u8; 4]], empty: &[[u8; 4]], lost: &[[u8; 4]], max_hearts: usize) -> HeartCounts {
+    let mut counts = HeartCounts::default();
+    for (kind, _) in find_heart_slots(view, full, empty, lost, max_hearts) {
+        match kind {
+            HeartKind::Full => counts.full += 1,
+            HeartKind::Empty => counts.empty += 1,
+            HeartKind::Lost => counts.lost += 1,
+        }
+    }
+    counts
+}
+
+/// the one of `colors` most of the view is drawn in (give or take `COLOR_TOLERANCE`).
+/// `None` if no pixel is any of them
+pub fn dominant_color(view: &SubImage<&DynamicImage>, colors: &[[u8; 4]]) -> Option<[u8; 4]> {
+    let counts: Vec<([u8; 4], usize)> = colors.iter()
+        .map(|c| (*c, view.pixels().filter(|(_, _, px)| color_matches(px.0, *c, COLOR_TOLERANCE)).count()))
+        .collect();
+    counts.into_iter().filter(|(_, count)| *count > 0).max_by_key(|(_, count)| *count).map(|(c, _)| c)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::RgbaImage;
+
+    const FULL: [u8; 4] = [253, 6, 6, 255];
+    const EMPTY: [u8; 4] = [75, 75, 85, 255];
+    const LOST: [u8; 4] = [120, 30, 30, 255];
+
+    /// a 20px tall heart row with a 14x16 heart of each color at the given x
+    fn heart_row(hearts: &[(u32, [u8; 4])]) -> DynamicImage {
+        let mut img = RgbaImage::from_pixel(120, 20, image::Rgba([40, 120, 200, 255]));
+        for (x, color) in hearts {
+            for py in 2..18 {
+                for px in *x..*x + 14 {
+                    img.put_pixel(px, py, image::Rgba(*color));
+                }
+            }
+        }
+        DynamicImage::ImageRgba8(img)
+    }
+
+    #[test]
+    fn counts_each_kind_of_heart() {
+        // slightly off colors and uneven spacing
+        let img = heart_row(&[(2, [240, 20, 14, 255]), (19, FULL), (40, LOST), (70, EMPTY), (86, [80, 70, 90, 255])]);
+        let counts = count_hearts(&img.view(0, 0, 120, 20), &[FULL], &[EMPTY], &[LOST], 10);
+        assert_eq!(counts, HeartCounts { full: 2, empty: 2, lost: 1 });
+        assert_eq!(counts.total(), 5);
+        // only the leftmost `max_hearts` count
+        assert_eq!(count_hearts(&img.view(0, 0, 120, 20), &[FULL], &[EMPTY], &[LOST], 3), HeartCounts { full: 2, empty: 0, lost: 1 });
+        assert_eq!(dominant_color(&img.view(0, 0, 120, 20), &[[232, 52, 37, 255], FULL]), Some(FULL));
+    }
+
+    #[test]
+    fn specks_are_not_hearts() {
+        let mut img = heart_row(&[(2, FULL)]).to_rgba8();
+        for px in 50..56 {
+            img.put_pixel(px, 10, image::Rgba(FULL));
+        }
+        let img = DynamicImage::ImageRgba8(img);
+        assert_eq!(find_hearts(&img.view(0, 0, 120, 20), &[FULL]).len(), 1);
+        assert_eq!(count_hearts(&img.view(20, 0, 100, 20), &[FULL], &[EMPTY], &[LOST], 10), HeartCounts::default());
+    }
+}
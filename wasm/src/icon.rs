@@ -0,0 +1,118 @@
+//! match colored UI icons (heart decorations...) against embedded reference icons.
+//! works the same way as the digit matcher: the region is scaled down to a small
+//! fixed size vector, and compared to every reference by euclidean distance
+
+use image::{imageops::FilterType::Triangle, DynamicImage, SubImage};
+
+/// icons are compared at `ICON_SIZE`x`ICON_SIZE` pixels
+pub const ICON_SIZE: u32 = 16;
+
+/// rgb values of an icon scaled down to `ICON_SIZE`x`ICON_SIZE`, row by row
+pub type IconVector = [u8; (ICON_SIZE * ICON_SIZE * 3) as usize];
+
+/// how many of the closest references of each label are averaged when classifying
+pub const ICON_NEIGHBOURS: usize = 3;
+
+/// references further away than this are not considered a match at all
+pub const MAX_ICON_DISTANCE: f64 = 0.15;
+
+pub fn icon_vector(view: &SubImage<&DynamicImage>) -> IconVector {
+    let scaled = image::imageops::resize(&view.to_image(), ICON_SIZE, ICON_SIZE, Triangle);
+    let mut vector = [0; (ICON_SIZE * ICON_SIZE * 3) as usize];
+    for (i, px) in scaled.pixels().enumerate() {
+        vector[i * 3..i * 3 + 3].copy_from_slice(&px.0[..3]);
+    }
+    vector
+}
+
+/// root mean square difference of every channel, from 0 (identical) to 1 (inverted)
+pub fn icon_distance(a: &IconVector, b: &IconVector) -> f64 {
+    let sum: f64 = a.iter().zip(b.iter())
+        .map(|(a, b)| ((*a as f64 - *b as f64) / 255.0).powi(2))
+        .sum();
+    (sum / a.len() as f64).sqrt()
+}
+
+/// every label of a set of references, in the order they first appear in it
+pub fn icon_labels<T: Copy + PartialEq>(references: &[(T, IconVector)]) -> Vec<T> {
+    let mut labels = vec![];
+    for (label, _) in references {
+        if !labels.contains(label) {
+            labels.push(*label);
+        }
+    }
+    labels
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IconMatch<T> {
+    pub label: T,
+    /// mean distance to the closest references of the label
+    pub distance: f64,
+    /// how much closer the best label is than the runner up.
+    /// if there is no runner up, how far the best label is within `MAX_ICON_DISTANCE`
+    pub confidence: f64,
+}
+
+/// the label whose `ICON_NEIGHBOURS` closest references are closest on average.
+/// `None` if there are no references, or even the best label is further than `MAX_ICON_DISTANCE`
+pub fn classify_icon<T: Copy + PartialEq>(vector: &IconVector, references: &[(T, IconVector)]) -> Option<IconMatch<T>> {
+    let mut distances: Vec<(T, Vec<f64>)> = vec![];
+    for (label, reference) in references {
+        let distance = icon_distance(vector, reference);
+        match distances.iter_mut().find(|(l, _)| l == label) {
+            Some((_, d)) => d.push(distance),
+            None => distances.push((*label, vec![distance])),
+        }
+    }
+    let mut scores: Vec<(T, f64)> = distances.into_iter()
+        .map(|(label, mut d)| {
+            d.sort_by(f64::total_cmp);
+            let k = d.len().min(ICON_NEIGHBOURS);
+            (label, d[..k].iter().sum::<f64>() / k as f64)
+        })
+        .collect();
+    scores.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let (label, distance) = *scores.first()?;
+    if distance > MAX_ICON_DISTANCE {
+        return None;
+    }
+    let runner_up = scores.get(1).map_or(MAX_ICON_DISTANCE, |(_, d)| *d);
+    Some(IconMatch { label, distance, confidence: runner_up - distance })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::{GenericImageView, RgbaImage};
+
+    /// a 32x32 icon of two colored halves
+    fn two_tone(left: [u8; 4], right: [u8; 4]) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(32, 32, |x, _| image::Rgba(if x < 16 { left } else { right })))
+    }
+
+    fn vector(img: &DynamicImage) -> IconVector {
+        icon_vector(&img.view(0, 0, img.width(), img.height()))
+    }
+
+    #[test]
+    fn closest_label_wins() {
+        let references = [
+            ("sun", vector(&two_tone([250, 200, 30, 255], [250, 120, 30, 255]))),
+            ("sea", vector(&two_tone([30, 90, 220, 255], [30, 180, 220, 255]))),
+        ];
+        // a slightly off, bigger rendering of the sun icon
+        let img = DynamicImage::ImageRgba8(image::imageops::resize(&two_tone([240, 205, 40, 255], [245, 110, 35, 255]), 50, 50, Triangle));
+        let found = classify_icon(&vector(&img), &references).expect("it should match");
+        assert_eq!(found.label, "sun");
+        assert!(found.confidence > 0.1);
+    }
+
+    #[test]
+    fn far_away_icons_do_not_match() {
+        let references = [("sun", vector(&two_tone([250, 200, 30, 255], [250, 120, 30, 255])))];
+        let img = two_tone([0, 0, 0, 255], [20, 20, 20, 255]);
+        assert_eq!(classify_icon(&vector(&img), &references), None);
+        assert_eq!(classify_icon::<&str>(&vector(&img), &[]), None);
+    }
+}
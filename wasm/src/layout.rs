@@ -0,0 +1,187 @@
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::level::{LevelView, PipColors};
+use crate::outcome::OutcomeColor;
+use crate::scene::SceneSignature;
+
+/// width and height (in pixels) of the game viewport that the built-in
+/// layout coordinates were originally measured on: a 2400x1080 screenshot
+/// with its 131px notch margin cropped off
+pub const REFERENCE_VIEWPORT: (u32, u32) = (2269, 1080);
+
+/// a rectangle expressed as fractions of the game viewport so that it
+/// can be scaled to whatever resolution the screenshot was taken at
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NormRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl NormRect {
+    /// build a normalized rect from pixel coordinates measured on the `REFERENCE_VIEWPORT`
+    pub const fn from_reference(x: u32, y: u32, width: u32, height: u32) -> Self {
+        let (ref_w, ref_h) = (REFERENCE_VIEWPORT.0 as f64, REFERENCE_VIEWPORT.1 as f64);
+        Self {
+            x: x as f64 / ref_w,
+            y: y as f64 / ref_h,
+            width: width as f64 / ref_w,
+            height: height as f64 / ref_h,
+        }
+    }
+
+    /// scale to pixel coordinates (x, y, width, height) of a viewport of the given size.
+    /// the result is clamped such that it always fits inside the viewport
+    pub fn to_pixels(&self, viewport_width: u32, viewport_height: u32) -> (u32, u32, u32, u32) {
+        let x = scale_coord(self.x, viewport_width);
+        let y = scale_coord(self.y, viewport_height);
+        let width = ((self.width * viewport_width as f64).round() as u32).clamp(1, viewport_width - x);
+        let height = ((self.height * viewport_height as f64).round() as u32).clamp(1, viewport_height - y);
+        (x, y, width, height)
+    }
+}
+
+/// a single point expressed as fractions of the game viewport
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NormPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl NormPoint {
+    /// build a normalized point from pixel coordinates measured on the `REFERENCE_VIEWPORT`
+    pub const fn from_reference(x: u32, y: u32) -> Self {
+        Self {
+            x: x as f64 / REFERENCE_VIEWPORT.0 as f64,
+            y: y as f64 / REFERENCE_VIEWPORT.1 as f64,
+        }
+    }
+
+    /// scale to pixel coordinates of a viewport of the given size
+    pub fn to_pixels(&self, viewport_width: u32, viewport_height: u32) -> (u32, u32) {
+        (scale_coord(self.x, viewport_width), scale_coord(self.y, viewport_height))
+    }
+}
+
+fn scale_coord(fraction: f64, size: u32) -> u32 {
+    ((fraction * size as f64).round() as u32).min(size - 1)
+}
+
+/// where everything we extract is located on the screen, and which colors
+/// we expect to find there. every position is normalized to the game viewport.
+/// profiles are plain json so that a game UI update only needs a new profile
+/// instead of a new build of the wasm module
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayoutProfile {
+    pub name: String,
+    /// region containing the row of hearts
+    pub heart_view: NormRect,
+    /// region containing the two digit turn counter
+    pub turn_number_view: NormRect,
+    /// colors a heart can be rendered in. differs between game versions
+    pub heart_colors: Vec<[u8; 4]>,
+    /// colors the hearts of lives lost in earlier turns are rendered in
+    pub empty_heart_colors: Vec<[u8; 4]>,
+    /// colors the heart of the life lost this turn is rendered in while it breaks
+    pub lost_heart_colors: Vec<[u8; 4]>,
+    /// the most lives a game can have. `heart_view` has to be wide enough for them all
+    pub max_hearts: usize,
+    /// region of the banner announcing the result at the end of a game
+    pub outcome_banner: NormRect,
+    /// the color the banner is drawn in for each outcome
+    pub outcome_colors: Vec<OutcomeColor>,
+    /// the UI elements that identify each screen of the game
+    pub scene_signatures: Vec<SceneSignature>,
+    /// where the level and experience of each team pet are drawn, left to right
+    pub level_views: Vec<LevelView>,
+    /// colors of the experience pips
+    pub pip_colors: PipColors,
+}
+
+/// (name, json) of the profiles that are compiled into the wasm module.
+/// the first one is the default
+pub const BUILTIN_PROFILES: &[(&str, &str)] = &[
+    ("default", include_str!("../profiles/default.json")),
+];
+
+impl LayoutProfile {
+    pub fn from_json(bytes: &[u8]) -> Result<Self, String> {
+        serde_json::from_slice(bytes).map_err(|e| e.to_string())
+    }
+
+    pub fn to_json(&self) -> String {
+        // a profile only contains plain numbers and strings, it always serializes
+        serde_json::to_string_pretty(self).expect("layout profile is always serializable")
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        let (_, json) = BUILTIN_PROFILES.iter().find(|(n, _)| *n == name)?;
+        // builtin profiles are covered by tests, so they always parse
+        Some(Self::from_json(json.as_bytes()).expect("builtin layout profile is valid json"))
+    }
+}
+
+impl Default for LayoutProfile {
+    fn default() -> Self {
+        Self::builtin(BUILTIN_PROFILES[0].0).expect("default profile exists")
+    }
+}
+
+/// the profile used by the wasm entrypoints. `None` means the default profile
+static ACTIVE_PROFILE: Mutex<Option<LayoutProfile>> = Mutex::new(None);
+
+pub fn active_profile() -> LayoutProfile {
+    let active = ACTIVE_PROFILE.lock().unwrap_or_else(|e| e.into_inner());
+    active.clone().unwrap_or_default()
+}
+
+pub fn set_active_profile(profile: LayoutProfile) {
+    let mut active = ACTIVE_PROFILE.lock().unwrap_or_else(|e| e.into_inner());
+    *active = Some(profile);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scene::Scene;
+
+    #[test]
+    fn builtin_profiles_parse() {
+        for (name, _) in BUILTIN_PROFILES {
+            let profile = LayoutProfile::builtin(name).expect("builtin profile exists");
+            assert_eq!(profile.name, *name);
+        }
+    }
+
+    #[test]
+    fn default_profile_matches_reference_layout() {
+        let profile = LayoutProfile::default();
+        assert_eq!(profile.heart_view.to_pixels(2269, 1080), (690, 892, 885, 133));
+        assert_eq!(profile.turn_number_view.to_pixels(2269, 1080), (1860, 790, 66, 49));
+        assert_eq!(profile.outcome_banner.to_pixels(2269, 1080), (735, 60, 800, 150));
+        let levels: Vec<_> = profile.level_views.iter()
+            .map(|v| (v.level.to_pixels(2269, 1080), v.experience.to_pixels(2269, 1080)))
+            .collect();
+        assert_eq!(levels, (0..5).map(|i| ((570 + 230 * i, 355, 40, 50), (620 + 230 * i, 370, 80, 20))).collect::<Vec<_>>());
+        let regions = |scene: Scene| -> Vec<_> {
+            profile.scene_signatures.iter().filter(|s| s.scene == scene)
+                .flat_map(|s| s.markers.iter().map(|m| m.region.to_pixels(2269, 1080)))
+                .collect()
+        };
+        assert_eq!(regions(Scene::EndScreen), vec![(735, 60, 800, 150); 3]);
+        assert_eq!(regions(Scene::Shop), vec![(40, 950, 250, 100), (1980, 950, 250, 100)]);
+        assert_eq!(regions(Scene::Battle), vec![(40, 950, 110, 100), (170, 950, 110, 100)]);
+        assert_eq!(regions(Scene::MainMenu), vec![(835, 120, 600, 220), (835, 700, 600, 160)]);
+    }
+
+    #[test]
+    fn profile_roundtrips_through_json() {
+        let profile = LayoutProfile { name: "shifted".to_string(), max_hearts: 8, ..LayoutProfile::default() };
+        let parsed = LayoutProfile::from_json(profile.to_json().as_bytes()).expect("valid json");
+        assert_eq!(parsed, profile);
+        assert!(LayoutProfile::from_json(b"{\"name\": \"incomplete\"}").is_err());
+    }
+}
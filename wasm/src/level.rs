@@ -0,0 +1,112 @@
+//! read the level of each team pet, and how much experience it has towards the next one.
+//! the level is a single digit, the experience a bar of pips below it: two pips
+//! at level 1, three at level 2, and none at level 3 since that is the max level
+
+use image::{DynamicImage, GenericImageView, SubImage};
+use serde::{Deserialize, Serialize};
+
+use crate::color::{color_matches, COLOR_TOLERANCE};
+use crate::components::Mask;
+use crate::{read_number, NormRect, TURN_COUNTER};
+
+pub const MAX_LEVEL: u8 = 3;
+
+/// how many experience pips the bar has at each level
+pub fn pips_at_level(level: u8) -> u8 {
+    match level {
+        1 => 2,
+        2 => 3,
+        _ => 0,
+    }
+}
+
+/// where the level and experience of one team slot are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LevelView {
+    /// region of the level digit
+    pub level: NormRect,
+    /// region of the bar of experience pips
+    pub experience: NormRect,
+}
+
+/// the colors an experience pip is drawn in
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PipColors {
+    pub filled: [u8; 4],
+    pub empty: [u8; 4],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PetLevel {
+    Known {
+        /// 1 to `MAX_LEVEL`
+        level: u8,
+        /// number of filled pips, towards the next level
+        experience: u8,
+    },
+    /// the level digit could not be read, or the pips do not add up to the
+    /// level (eg: something is drawn over them)
+    Unknown,
+}
+
+/// pips smaller than this fraction of the bar are specks, not pips
+const MIN_PIP_FRACTION: f64 = 0.03;
+
+/// count the (filled, empty) pips in a bar
+fn count_pips(bar: &SubImage<&DynamicImage>, colors: &PipColors) -> (u8, u8) {
+    let (width, height) = bar.dimensions();
+    let min_area = (width * height) as f64 * MIN_PIP_FRACTION;
+    let count = |color: [u8; 4]| {
+        let mask = Mask::new(width, height, |x, y| color_matches(bar.get_pixel(x, y).0, color, COLOR_TOLERANCE));
+        mask.connected_components().iter().filter(|c| c.area as f64 >= min_area).count() as u8
+    };
+    (count(colors.filled), count(colors.empty))
+}
+
+pub fn read_level(level_view: &SubImage<&DynamicImage>, experience_view: &SubImage<&DynamicImage>, colors: &PipColors) -> PetLevel {
+    let Some(level) = read_number(level_view, &TURN_COUNTER) else {
+        return PetLevel::Unknown;
+    };
+    let Ok(level) = u8::try_from(level.value) else {
+        return PetLevel::Unknown;
+    };
+    if !(1..=MAX_LEVEL).contains(&level) {
+        return PetLevel::Unknown;
+    }
+    let (filled, empty) = count_pips(experience_view, colors);
+    if filled + empty != pips_at_level(level) {
+        return PetLevel::Unknown;
+    }
+    PetLevel::Known { level, experience: filled }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::RgbaImage;
+
+    const COLORS: PipColors = PipColors { filled: [250, 170, 40, 255], empty: [90, 70, 50, 255] };
+
+    /// a bar of pips, the first `filled` of them filled
+    fn bar(pips: u32, filled: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(60, 12, |x, y| {
+            let pip_width = 60 / pips.max(1);
+            let pip = x / pip_width;
+            let in_pip = pips > 0 && x % pip_width >= 2 && (2..10).contains(&y);
+            image::Rgba(match (in_pip, pip < filled) {
+                (false, _) => [40, 120, 200, 255],
+                (true, true) => COLORS.filled,
+                (true, false) => COLORS.empty,
+            })
+        }))
+    }
+
+    #[test]
+    fn counts_filled_and_empty_pips() {
+        for (pips, filled) in [(2, 0), (2, 1), (3, 2), (0, 0)] {
+            let img = bar(pips, filled);
+            let counted = count_pips(&img.view(0, 0, 60, 12), &COLORS);
+            assert_eq!(counted, (filled as u8, (pips - filled) as u8));
+        }
+    }
+}
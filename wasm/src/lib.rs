@@ -1,20 +1,33 @@
-use std::ops::BitAnd;
-
 use image::{imageops::FilterType::Nearest, DynamicImage, GenericImageView, SubImage};
 
+mod layout;
+pub use layout::*;
+pub mod calibrate;
+mod components;
+mod error;
+pub use error::ExtractError;
+pub mod record;
+pub mod threshold;
+mod number;
+pub use number::*;
+pub mod color;
+pub mod outcome;
+pub use outcome::Outcome;
+pub mod icon;
+pub mod level;
+pub use level::PetLevel;
+pub mod hearts;
+pub mod decoration;
+mod heart_decorations;
+pub use decoration::HeartDecoration;
+pub mod scene;
+pub use scene::{Scene, SceneReading};
+
 pub const BLACK: [u8; 4] = [0,0,0,255];
 pub const WHITE: [u8; 4] = [255,255,255,255];
 
-pub const VEC_0: [u8; 256] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 0, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-pub const VEC_1: [u8; 256] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-pub const VEC_2: [u8; 256] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-pub const VEC_3: [u8; 256] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-pub const VEC_4: [u8; 256] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-pub const VEC_5: [u8; 256] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0];
-pub const VEC_6: [u8; 256] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-pub const VEC_7: [u8; 256] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-pub const VEC_8: [u8; 256] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0];
-pub const VEC_9: [u8; 256] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+mod digit_templates;
+pub use digit_templates::*;
 
 pub fn vector_distance(vec1: &[u8; 256], vec2: &[u8; 256]) -> f64 {
     vec1.iter().zip(vec2.iter())
@@ -23,6 +36,7 @@ pub fn vector_distance(vec1: &[u8; 256], vec2: &[u8; 256]) -> f64 {
         .sqrt()
 }
 
+#[allow(dead_code)]
 fn get_image_width(bytes: &[u8]) -> Result<u32, String> {
     let img = image::load_from_memory(bytes).map_err(|e| e.to_string())?;
     Ok(img.width())
@@ -30,71 +44,130 @@ fn get_image_width(bytes: &[u8]) -> Result<u32, String> {
 
 pub struct ImageData {
     pub heart_data: HeartData,
+    pub outcome: Outcome,
+    /// level and experience of the pet in each team slot, left to right
+    pub team_levels: Vec<PetLevel>,
     pub turn_number: u8,
+    /// how sure we are of each digit of the turn number, left to right
+    pub turn_digits: Vec<DigitReading>,
+    /// which screen of the game this looks like. only reported, nothing is rejected on it
+    pub scene: SceneReading,
 }
 
-fn get_image_data(bytes: &[u8]) -> Result<ImageData, String> {
-    let img = image::load_from_memory(bytes).map_err(|e| e.to_string())?;
-    let probable_pets_img = probable_pets_image_check(&img).ok_or(format!("not a pets img"))?;
-    let pets_img = PetsImage::new(&probable_pets_img).ok_or(format!("not a pets img"))?;
-    let turn_number = pets_img.get_turn_number();
+fn get_image_data(bytes: &[u8]) -> Result<ImageData, ExtractError> {
+    let img = image::load_from_memory(bytes)?;
+    let probable_pets_img = probable_pets_image_check(&img)?;
+    let layout = active_profile();
+    let pets_img = PetsImage::new(&probable_pets_img, &layout);
+    let TurnNumber::Read { value, digits } = pets_img.get_turn_number() else {
+        return Err(ExtractError::DigitSegmentation);
+    };
+    let scene = pets_img.get_scene();
     Ok(ImageData {
+        outcome: pets_img.get_outcome(),
+        team_levels: pets_img.get_team_levels(),
         heart_data: pets_img.heart_data,
-        turn_number,
+        turn_number: value,
+        turn_digits: digits,
+        scene,
     })
 }
 
-pub fn get_pets_img<T>(bytes: &[u8], cb: impl FnOnce(PetsImage) -> T) -> Result<T, String> {
-    let img = image::load_from_memory(bytes).map_err(|e| e.to_string())?;
-    let probable_pets_img = probable_pets_image_check(&img).ok_or(format!("not a pets img"))?;
-    let pets_img = PetsImage::new(&probable_pets_img).ok_or(format!("not a pets img"))?;
+pub fn get_pets_img<T>(bytes: &[u8], cb: impl FnOnce(PetsImage) -> T) -> Result<T, ExtractError> {
+    let img = image::load_from_memory(bytes)?;
+    let probable_pets_img = probable_pets_image_check(&img)?;
+    let layout = active_profile();
+    let pets_img = PetsImage::new(&probable_pets_img, &layout);
     let t = cb(pets_img);
 
     Ok(t)
 }
 
 
+/// buffers handed across the wasm boundary are boxed slices, so that their
+/// length is all that is needed to free them again
 #[unsafe(no_mangle)]
 extern "C" fn alloc(len: usize) -> *mut u8 {
-    let mut buffer = Vec::with_capacity(len);
-    let ptr = buffer.as_mut_ptr();
-    std::mem::forget(buffer);
-    ptr
+    Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8
 }
 
 #[unsafe(no_mangle)]
-extern "C" fn wasm_entrypoint(ptr: *mut u8, len: usize) -> i64 {
-    let bytes: Vec<u8> = unsafe { Vec::from_raw_parts(ptr, len, len) };
-
-    match get_image_data(&bytes) {
-        Ok(o) => {
-            // wasm can return primitive types only, we transform our
-            // data we're interested into a number
-
-            // 3 least significant bits can represent 0-5
-            // 1 bits to the left we use to denote has_bandage or not
-            let has_bandage: i64 = 0b1000;
-            let mut out = o.heart_data.num_hearts as i64;
-            if o.heart_data.has_bandage {
-                out += has_bandage;
-            }
-            // next, the turn number is a number usually between 10 and 30... it can be more
-            // but highly unlikely.
-            // we encode this in 5 bits. the first bit is whether its >= 20.
-            // the next 4 bits encode the last digit 0 - 9.
-            // 01000 => 18
-            // 10000 => 20
-            // 10001 => 21
-            // 00001 => 11
-            let over_20: i64 = 0b10000;
-            let mut turn_count = (o.turn_number as i64 - 10).bitand(0b1111);
-            if o.turn_number >= 20 {
-                turn_count += over_20;
-            }
-            // shift the turn count to be left of the heart info
-            // 0b1111 <- heart info
-            //     0b11111 <- turn count
-            out + (turn_count << 4)
+extern "C" fn dealloc(ptr: *mut u8, len: usize) {
+    drop(unsafe { take_buffer(ptr, len) });
+}
+
+/// take back ownership of a buffer returned by `alloc` or `write_out`
+unsafe fn take_buffer(ptr: *mut u8, len: usize) -> Box<[u8]> {
+    unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)) }
+}
+
+/// extract everything we know about a screenshot. the result is a
+/// `record::ExtractionRecord` written into wasm memory. its location is
+/// written to `out` as [pointer, length in bytes]. the caller must `dealloc` it
+#[unsafe(no_mangle)]
+extern "C" fn wasm_extract(ptr: *mut u8, len: usize, out: *mut [usize; 2]) {
+    let bytes = unsafe { take_buffer(ptr, len) };
+
+    let record = record::ExtractionRecord::from_result(&get_image_data(&bytes));
+    write_out(record.to_bytes(), out);
+}
+
+/// hand a buffer over to javascript by writing its [pointer, length in bytes] to `out`.
+/// the caller must `dealloc` it. `out` comes from `alloc`, so it is not necessarily aligned
+fn write_out(bytes: Vec<u8>, out: *mut [usize; 2]) {
+    let len = bytes.len();
+    let ptr = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+    unsafe { out.write_unaligned([ptr as usize, len]) };
+}
+
+/// the names of the heart slot decorations, one per line. the decoration id `n` in
+/// the extraction record is the `n`th line (counting from 1). written to `out` as
+/// [pointer, length in bytes]. the caller must `dealloc` it
+#[unsafe(no_mangle)]
+extern "C" fn wasm_heart_decoration_names(out: *mut [usize; 2]) {
+    write_out(decoration::decoration_names().join("\n").into_bytes(), out);
+}
+
+/// replace the layout profile used by `wasm_extract` with a custom one.
+/// the bytes must be a json encoded `LayoutProfile`.
+/// returns 0 on success, -1 if the profile could not be parsed
+#[unsafe(no_mangle)]
+extern "C" fn wasm_load_layout_profile(ptr: *mut u8, len: usize) -> i32 {
+    let bytes = unsafe { take_buffer(ptr, len) };
+
+    match LayoutProfile::from_json(&bytes) {
+        Ok(profile) => {
+            set_active_profile(profile);
+            0
+        }
+        Err(_) => -1,
+    }
+}
+
+/// switch to one of the `BUILTIN_PROFILES` by its index.
+/// returns 0 on success, -1 if there is no builtin profile with that index
+#[unsafe(no_mangle)]
+extern "C" fn wasm_use_builtin_layout_profile(index: usize) -> i32 {
+    match BUILTIN_PROFILES.get(index).and_then(|(name, _)| LayoutProfile::builtin(name)) {
+        Some(profile) => {
+            set_active_profile(profile);
+            0
+        }
+        None => -1,
+    }
+}
+
+/// calibrate a new layout profile from a known-good win screenshot and
+/// make it the active profile. the current profile is used as the starting point.
+/// returns 0 on success, -1 if the hearts or turn number could not be found
+#[unsafe(no_mangle)]
+extern "C" fn wasm_calibrate_layout_profile(ptr: *mut u8, len: usize) -> i32 {
+    let bytes = unsafe { take_buffer(ptr, len) };
+
+    match calibrate::calibrate_from_bytes(&bytes, &active_profile()) {
+        Ok(profile) => {
+            set_active_profile(profile);
+            0
         }
         Err(_) => -1,
     }
@@ -113,95 +186,111 @@ extern "C" fn wasm_debug_sum(ptr: *const u8, len: usize) -> u32 {
 }
 
 /// we can get reasonably close to knowing a screenshot is of SAP
-/// by checking both margins at 131px and if they are 100% black on the sides
-/// then its likely a pets image
+/// by measuring the solid black margins that the game leaves on the sides
+/// of the screen for the notch/cutout. the margins are found by scanning
+/// inward from each edge for the first column that is not 100% black.
+/// devices without a notch have no margin at all, in which case
+/// the whole screenshot is the game viewport
 pub struct ProbablyPetsImage<'a> {
-    pub margin_size: u32,
-    /// most will be on left. a few have this margin on the right. if false => right
-    pub margin_is_on_left: bool,
+    /// width of the black margin on the left side of the screenshot
+    pub left_margin: u32,
+    /// width of the black margin on the right side of the screenshot
+    pub right_margin: u32,
+    /// the screenshot with both margins cropped off
     pub view: SubImage<&'a DynamicImage>,
 }
 
-fn probable_pets_image_check<'a>(img: &'a DynamicImage) -> Option<ProbablyPetsImage<'a>> {
-    let margin_size = 131;
+impl ProbablyPetsImage<'_> {
+    /// most will have the margin on the left. a few have it on the right.
+    pub fn margin_is_on_left(&self) -> bool {
+        self.left_margin >= self.right_margin
+    }
+}
+
+/// margins are never wider than this fraction of the screenshot. stops us from
+/// scanning across (almost) entirely black images
+const MAX_MARGIN_FRACTION: f64 = 0.25;
+
+fn probable_pets_image_check<'a>(img: &'a DynamicImage) -> Result<ProbablyPetsImage<'a>, ExtractError> {
     let img_height = img.height();
     let img_width = img.width();
-    if img_width != 2400 || img_height != 1080 {
-        return None;
+    // the game is always played in landscape
+    if img_width <= img_height {
+        return Err(ExtractError::WrongDimensions { width: img_width, height: img_height });
     }
-    let mut margin_is_on_left = true;
-    for y in 0..img_height {
-        let margin_pixel = img.get_pixel(margin_size, y);
-        if margin_pixel.0 != BLACK {
-            margin_is_on_left = false;
-            break;
-        }
-    }
-    if !margin_is_on_left {
-        for y in 0..img_height {
-            let margin_pixel = img.get_pixel(img_width - margin_size, y);
-            if margin_pixel.0 != BLACK {
-                return None;
-            }
-        }
-    }
-    let view = if margin_is_on_left {
-        img.view(margin_size, 0, img_width - margin_size, img_height)
-    } else {
-        img.view(0, 0, img_width - margin_size, img_height)
-    };
+    let max_margin = (img_width as f64 * MAX_MARGIN_FRACTION) as u32;
+    let column_is_black = |x: u32| (0..img_height).all(|y| img.get_pixel(x, y).0 == BLACK);
+    let left_margin = (0..max_margin).find(|x| !column_is_black(*x)).ok_or(ExtractError::MissingMargin)?;
+    let right_margin = (0..max_margin).find(|x| !column_is_black(img_width - 1 - *x)).ok_or(ExtractError::MissingMargin)?;
+    let view = img.view(left_margin, 0, img_width - left_margin - right_margin, img_height);
 
-    Some(ProbablyPetsImage { margin_size, margin_is_on_left, view })
+    Ok(ProbablyPetsImage { left_margin, right_margin, view })
 }
 
 pub struct PetsImage<'a> {
     pub view: SubImage<&'a DynamicImage>,
     pub heart_data: HeartData,
+    /// where to find each region of interest within the view
+    pub layout: &'a LayoutProfile,
 }
 
 impl<'a> PetsImage<'a> {
-    pub fn new(probpets: &'a ProbablyPetsImage<'a>) -> Option<Self> {
-        let heart_data = handle_heart_check(probpets)?;
-        Some(Self { view: probpets.view, heart_data })
+    pub fn new(probpets: &'a ProbablyPetsImage<'a>, layout: &'a LayoutProfile) -> Self {
+        // screens without hearts (a loss with no lives left, or a versus end
+        // screen, which shows a ranking instead) have zero hearts
+        let heart_data = handle_heart_check(probpets, layout).unwrap_or(HeartData::NONE);
+        Self { view: probpets.view, heart_data, layout }
     }
     pub fn get_sub_view(&self, view: (u32, u32, u32, u32)) -> SubImage<&DynamicImage> {
         self.view.view(view.0, view.1, view.2, view.3)
     }
+    /// get a sub view of a region expressed in normalized viewport coordinates
+    pub fn get_norm_sub_view(&self, rect: &NormRect) -> SubImage<&DynamicImage> {
+        self.get_sub_view(rect.to_pixels(self.view.width(), self.view.height()))
+    }
     pub fn get_turn_number_view(&self) -> SubImage<&DynamicImage> {
-        self.get_sub_view((1860, 790, 66, 49))
+        self.get_norm_sub_view(&self.layout.turn_number_view)
+    }
+    pub fn get_outcome(&self) -> Outcome {
+        let banner = self.get_norm_sub_view(&self.layout.outcome_banner);
+        outcome::classify_outcome(&banner, &self.layout.outcome_colors)
+    }
+    /// which screen of the game this is, by the `scene_signatures` of the
+    /// layout profile, see `scene::classify_scene`
+    pub fn get_scene(&self) -> SceneReading {
+        scene::classify_scene(&self.view, &self.layout.scene_signatures)
+    }
+    /// a view of each heart slot of the heart row, left to right
+    pub fn get_heart_slot_views(&self) -> Vec<SubImage<&DynamicImage>> {
+        heart_slots(&self.view, self.layout).into_iter().map(|(_, (x, y, w, h))| self.get_sub_view((x, y, w, h))).collect()
+    }
+    pub fn get_heart_slot_vectors(&self) -> Vec<icon::IconVector> {
+        self.get_heart_slot_views().iter().map(icon::icon_vector).collect()
+    }
+    pub fn get_team_levels(&self) -> Vec<PetLevel> {
+        self.layout.level_views.iter()
+            .map(|view| {
+                let level = self.get_norm_sub_view(&view.level);
+                let experience = self.get_norm_sub_view(&view.experience);
+                level::read_level(&level, &experience, &self.layout.pip_colors)
+            })
+            .collect()
     }
     pub fn get_digits(&self) -> Option<(DynamicImage, DynamicImage)> {
-        let view = self.get_turn_number_view();
-        let (left, right) = extract_digit_clips(&view)?;
-        let mut first_digit = left.to_image();
-        let mut second_digit = right.to_image();
-        first_digit.pixels_mut().for_each(|px| {
-            if px.0 != WHITE {
-                px.0 = BLACK;
-            }
-        });
-        second_digit.pixels_mut().for_each(|px| {
-            if px.0 != WHITE {
-                px.0 = BLACK;
-            }
-        });
-        let first_digit = image::imageops::resize(&first_digit, 16, 16, Nearest);
-        let second_digit = image::imageops::resize(&second_digit, 16, 16, Nearest);
-        let first_img = DynamicImage::ImageRgba8(first_digit);
-        let second_img = DynamicImage::ImageRgba8(second_digit);
-        Some((first_img, second_img))
+        get_digits_from_view(&self.get_turn_number_view())
     }
+    /// a pixel of a digit is set if it is on the bright side of the mask
     pub fn get_digits_vectors_from_two_dyn_images(images: (DynamicImage, DynamicImage)) -> ([u8; 256], [u8; 256]) {
         let (left, right) = images;
         let mut first_digit_vector: [u8; 256] = [0; 256];
         for (i, (_, _, p)) in left.pixels().enumerate() {
-            if p.0 == WHITE {
+            if threshold::luma(p.0) > 127 {
                 first_digit_vector[i] = 1;
             }
         }
         let mut second_digit_vector: [u8; 256] = [0; 256];
         for (i, (_, _, p)) in right.pixels().enumerate() {
-            if p.0 == WHITE {
+            if threshold::luma(p.0) > 127 {
                 second_digit_vector[i] = 1;
             }
         }
@@ -212,50 +301,132 @@ impl<'a> PetsImage<'a> {
             Self::get_digits_vectors_from_two_dyn_images((left, right))
         })
     }
-    pub fn get_turn_number(&self) -> u8 {
-        let (left, right) = match self.get_digits_vectors() {
-            Some(x) => x,
-            None => return 0,
+    pub fn get_turn_number(&self) -> TurnNumber {
+        let Some(reading) = read_number(&self.get_turn_number_view(), &TURN_COUNTER) else {
+            return TurnNumber::Unreadable;
         };
-        let base = get_digit(&left);
-        let second_digit = get_digit(&right);
-        return base * 10 + second_digit;
+        match u8::try_from(reading.value) {
+            Ok(value) => TurnNumber::Read { value, digits: reading.digits },
+            Err(_) => TurnNumber::Unreadable,
+        }
+    }
+}
+
+/// the result of reading the turn counter
+#[derive(Debug, Clone, PartialEq)]
+pub enum TurnNumber {
+    Read {
+        value: u8,
+        /// each digit of the value, left to right
+        digits: Vec<DigitReading>,
+    },
+    /// there are no digits in the turn counter, or too many to be a turn number
+    Unreadable,
+}
+
+impl TurnNumber {
+    pub fn value(&self) -> Option<u8> {
+        match self {
+            TurnNumber::Read { value, .. } => Some(*value),
+            TurnNumber::Unreadable => None,
+        }
+    }
+
+    /// confidence of the least certain digit. 0 if unreadable
+    pub fn confidence(&self) -> f64 {
+        match self {
+            TurnNumber::Read { digits, .. } => digits.iter().map(|d| d.confidence).fold(f64::MAX, f64::min),
+            TurnNumber::Unreadable => 0.0,
+        }
     }
 }
 
+/// a single classified digit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DigitReading {
+    pub digit: u8,
+    /// how much closer the digit is to its best matching template than to the
+    /// second best one (see `get_digit_similarity`). 0 means two templates
+    /// matched equally well, so the digit could just as well be the other one
+    pub confidence: f64,
+}
+
+/// clip the two digits out of a view of a two digit number, turn everything that
+/// is not part of a digit black and scale each digit to 16x16.
+/// the view is binarized first (see `threshold::binarize`), so the digits only
+/// need to be clearly brighter than the box around them, not exactly white
+pub fn get_digits_from_view(view: &SubImage<&DynamicImage>) -> Option<(DynamicImage, DynamicImage)> {
+    let mask = threshold::binarize(view);
+    let mask_view = mask.view(0, 0, mask.width(), mask.height());
+    let (left, right) = extract_digit_clips(&mask_view)?;
+    let first_digit = image::imageops::resize(&left.to_image(), 16, 16, Nearest);
+    let second_digit = image::imageops::resize(&right.to_image(), 16, 16, Nearest);
+    let first_img = DynamicImage::ImageRgba8(first_digit);
+    let second_img = DynamicImage::ImageRgba8(second_digit);
+    Some((first_img, second_img))
+}
+
 pub fn get_digit(digit: &[u8; 256]) -> u8 {
-    let breakdown = get_digit_similarity(digit);
+    read_digit(digit).digit
+}
+
+/// classify a digit, keeping track of how close the runner up was
+pub fn read_digit(digit: &[u8; 256]) -> DigitReading {
+    read_digit_with_samples(digit, DIGIT_SAMPLES)
+}
+
+/// same as `read_digit` but against any set of (digit, sample) reference vectors
+pub fn read_digit_with_samples(digit: &[u8; 256], samples: &[(u8, [u8; 256])]) -> DigitReading {
+    let breakdown = get_digit_similarity_with_samples(digit, samples);
     let mut lowest_value = 9999.0;
+    let mut second_lowest_value = 9999.0;
     let mut lowest_index = 11;
     for (i, b) in breakdown.into_iter().enumerate() {
         if b < lowest_value {
+            second_lowest_value = lowest_value;
             lowest_value = b;
             lowest_index = i as u8;
+        } else if b < second_lowest_value {
+            second_lowest_value = b;
         }
     }
-    lowest_index
+    DigitReading { digit: lowest_index, confidence: second_lowest_value - lowest_value }
 }
 
+/// how many of the closest reference samples of each digit are averaged when classifying
+pub const DIGIT_NEIGHBOURS: usize = 3;
+
 /// returns an array of 10 floats, each representing the distance between
 /// the current digit and the digit of the index.
 /// for example [1,1,1,1,1,0,1,1,1,1]
 /// would imply the current digit has a distance of 0 from digit 5 (index 5)
 /// and a distance of 1 for the rest. this implies the current digit is most likely a 5
+///
+/// the distance to a digit is the mean distance to the `DIGIT_NEIGHBOURS` closest
+/// reference samples of that digit in `DIGIT_SAMPLES`, so one odd looking sample
+/// (or one odd pixel of anti-aliasing) cannot decide the result on its own
 pub fn get_digit_similarity(current_digit: &[u8; 256]) -> [f64; 10] {
-    [
-        VEC_0,
-        VEC_1,
-        VEC_2,
-        VEC_3,
-        VEC_4,
-        VEC_5,
-        VEC_6,
-        VEC_7,
-        VEC_8,
-        VEC_9
-    ].map(|x| vector_distance(current_digit, &x))
+    get_digit_similarity_with_samples(current_digit, DIGIT_SAMPLES)
+}
+
+/// same as `get_digit_similarity` but against any set of (digit, sample) reference vectors
+pub fn get_digit_similarity_with_samples(current_digit: &[u8; 256], samples: &[(u8, [u8; 256])]) -> [f64; 10] {
+    let mut distances: [Vec<f64>; 10] = Default::default();
+    for (digit, sample) in samples {
+        distances[*digit as usize].push(vector_distance(current_digit, sample));
+    }
+    distances.map(|mut digit_distances| {
+        if digit_distances.is_empty() {
+            return f64::INFINITY;
+        }
+        digit_distances.sort_by(f64::total_cmp);
+        let k = digit_distances.len().min(DIGIT_NEIGHBOURS);
+        digit_distances[..k].iter().sum::<f64>() / k as f64
+    })
 }
 
+/// find the next digit at or after `start_x` in a binarized view (see `threshold::binarize`).
+/// returns the column the digit ends at along with a view of the digit
 pub fn extract_digit_zone<'a>(view: &'a SubImage<&'a DynamicImage>, start_x: u32) -> Option<(u32, SubImage<&'a DynamicImage>)> {
     // setup mapping function to treat anything non white as black
     let get_px = |x: u32, y: u32| {
@@ -294,62 +465,106 @@ pub fn extract_digit_zone<'a>(view: &'a SubImage<&'a DynamicImage>, start_x: u32
             break;
         }
     }
-    let digit_end = digit_end?;
+    // a digit touching the right edge ends there
+    let digit_end = digit_end.unwrap_or(width);
     let v = view.view(digit_start, 0, digit_end - digit_start, height);
     Some((digit_end, v))
 }
 
 pub fn extract_digit_clips<'a>(view: &'a SubImage<&'a DynamicImage>) -> Option<(SubImage<&'a DynamicImage>, SubImage<&'a DynamicImage>)> {
-    let (next_digit_start_x, first_digit_view) = extract_digit_zone(&view, 0)?;
-    let (_, second_digit_view) = extract_digit_zone(&view, next_digit_start_x)?;
+    let (next_digit_start_x, first_digit_view) = extract_digit_zone(view, 0)?;
+    let (_, second_digit_view) = extract_digit_zone(view, next_digit_start_x)?;
 
     Some((first_digit_view, second_digit_view))
 }
 
 pub struct HeartData {
-    pub valid_red_color: [u8; 4],
+    /// the heart color most of the full hearts are drawn in, `None` for screens without full hearts
+    pub valid_red_color: Option<[u8; 4]>,
+    /// full hearts, the lives left
     pub num_hearts: usize,
-    pub has_bandage: bool,
-}
-
-fn handle_heart_check<'a>(img: &'a ProbablyPetsImage<'a>) -> Option<HeartData> {
-    let heart_view_x = 690;
-    let heart_view_y = 892;
-    let heart_view_width = 885;
-    let heart_view_height = 133;
-
-    let heart_bandage_x = 75;
-    let heart_bandage_y = 60;
-
-    let heart_view = img.view.view(heart_view_x, heart_view_y, heart_view_width, heart_view_height);
-
-    let valid_red_heart_colors: [[u8; 4]; 2] = [
-        [253, 6, 6, 255],
-        [232, 52, 37, 255],
-    ];
-    let first_heart_pixel_x = 34;
-    let pixel1 = heart_view.get_pixel(first_heart_pixel_x, first_heart_pixel_x);
-    // return None if we are not looking at a pets screenshot:
-    // if its not a red pixel where we expect there to be one
-    let valid_red_color = valid_red_heart_colors.iter().find(|x| pixel1.0 == **x)?;
-    let valid_red_color = *valid_red_color;
-    let pixel_bandage = heart_view.get_pixel(heart_bandage_x, heart_bandage_y);
-    let has_bandage = pixel_bandage.0 != valid_red_color;
-    let heart_distance = 184;
-    let mut num_hearts = 1;
-    for i in 1..=4 {
-        let heart_pixel_x = first_heart_pixel_x + heart_distance * i;
-        let pixel_next = heart_view.get_pixel(heart_pixel_x, first_heart_pixel_x);
-        if pixel_next.0 == valid_red_color {
-            num_hearts += 1;
-        }
+    /// hearts of lives lost in earlier turns
+    pub empty_hearts: usize,
+    /// hearts of lives lost this turn
+    pub lost_hearts: usize,
+    /// icons drawn over the heart slots, left to right
+    pub decorations: Vec<HeartDecoration>,
+}
+
+impl HeartData {
+    /// what we know about the hearts of a screen that has none
+    pub const NONE: HeartData = HeartData { valid_red_color: None, num_hearts: 0, empty_hearts: 0, lost_hearts: 0, decorations: vec![] };
+
+    pub fn has_bandage(&self) -> bool {
+        self.decorations.iter().any(|d| d.name == decoration::BANDAGE)
     }
-    Some(HeartData { valid_red_color, num_hearts, has_bandage })
+}
+
+/// the kind of a heart and its rect in viewport pixels
+type HeartSlot = (hearts::HeartKind, (u32, u32, u32, u32));
+
+/// the heart slots of the heart row, left to right
+fn heart_slots(view: &SubImage<&DynamicImage>, layout: &LayoutProfile) -> Vec<HeartSlot> {
+    let (x, y, w, h) = layout.heart_view.to_pixels(view.width(), view.height());
+    let heart_view = view.view(x, y, w, h);
+    hearts::find_heart_slots(&heart_view, &layout.heart_colors, &layout.empty_heart_colors, &layout.lost_heart_colors, layout.max_hearts)
+        .into_iter()
+        .map(|(kind, c)| (kind, (x + c.min_x, y + c.min_y, c.width(), c.height())))
+        .collect()
+}
+
+/// count the hearts in the heart row (see `hearts`) and find the icons drawn over them (see `decoration`)
+fn handle_heart_check<'a>(img: &'a ProbablyPetsImage<'a>, layout: &LayoutProfile) -> Option<HeartData> {
+    let slots = heart_slots(&img.view, layout);
+    // return None if there are no hearts of any kind where the heart row should be
+    if slots.is_empty() {
+        return None;
+    }
+    let count = |kind: hearts::HeartKind| slots.iter().filter(|(k, _)| *k == kind).count();
+    let (x, y, w, h) = layout.heart_view.to_pixels(img.view.width(), img.view.height());
+    let valid_red_color = hearts::dominant_color(&img.view.view(x, y, w, h), &layout.heart_colors);
+    let decorations = if decoration::HEART_DECORATIONS.is_empty() {
+        // there are no references to match against yet, fall back to the bandage probe
+        probe_bandage(img, layout, &slots, valid_red_color).into_iter().collect()
+    } else {
+        let views: Vec<_> = slots.iter().map(|(_, (x, y, w, h))| img.view.view(*x, *y, *w, *h)).collect();
+        decoration::detect_decorations(&views)
+    };
+    Some(HeartData {
+        valid_red_color,
+        num_hearts: count(hearts::HeartKind::Full),
+        empty_hearts: count(hearts::HeartKind::Empty),
+        lost_hearts: count(hearts::HeartKind::Lost),
+        decorations,
+    })
+}
+
+/// the pixel of the first heart a bandage covers, measured on the 133px high heart row of
+/// the reference viewport. both coordinates scale with the height of the heart row, as the
+/// hearts keep their shape whatever the width of the screen
+const BANDAGE_PROBE: (u32, u32) = (75, 60);
+const BANDAGE_PROBE_ROW_HEIGHT: u32 = 133;
+
+/// a bandage is drawn over the first heart, so there is one if the bandage probe
+/// is inside the first full heart but not heart colored
+fn probe_bandage(img: &ProbablyPetsImage, layout: &LayoutProfile, slots: &[HeartSlot], color: Option<[u8; 4]>) -> Option<HeartDecoration> {
+    let color = color?;
+    let (slot, (_, (x, y, w, h))) = slots.iter().enumerate().find(|(_, (kind, _))| *kind == hearts::HeartKind::Full)?;
+    let (row_x, row_y, _, row_height) = layout.heart_view.to_pixels(img.view.width(), img.view.height());
+    let scale = |offset: u32| (offset as f64 * row_height as f64 / BANDAGE_PROBE_ROW_HEIGHT as f64).round() as u32;
+    let (probe_x, probe_y) = (row_x + scale(BANDAGE_PROBE.0), row_y + scale(BANDAGE_PROBE.1));
+    let inside = (*x..x + w).contains(&probe_x) && (*y..y + h).contains(&probe_y);
+    let covered = !color::color_matches(img.view.get_pixel(probe_x, probe_y).0, color, color::COLOR_TOLERANCE);
+    (inside && covered).then_some(HeartDecoration { slot, name: decoration::BANDAGE, confidence: 0.0 })
 }
 
+#[cfg(test)]
+mod testutil;
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use testutil::SynthScreenshot;
 
     fn get_probable_pets_img(filename: &str, t: impl Fn(ProbablyPetsImage)) {
         let data = std::fs::read(format!("./testfixtures/{}", filename)).expect("failed to read test fixture");
@@ -358,45 +573,365 @@ mod test {
         t(petsimg);
     }
 
+    #[allow(dead_code)]
     fn get_pets_img(filename: &str, t: impl Fn(PetsImage)) {
         let data = std::fs::read(format!("./testfixtures/{}", filename)).expect("failed to read test fixture");
         let img = image::load_from_memory(&data).expect("failed to load test fixture img");
         let petsimg = probable_pets_image_check(&img).expect("it should be a pets img");
-        let petsimg = PetsImage::new(&petsimg).expect("failed to get pets img");
+        let layout = LayoutProfile::default();
+        let petsimg = PetsImage::new(&petsimg, &layout);
         t(petsimg);
     }
 
     #[test]
     fn can_detect_margin_position() {
         get_probable_pets_img("Screenshot_20240714-090957.png", |img| {
-            assert!(img.margin_is_on_left);
+            assert!(img.margin_is_on_left());
         });
 
         get_probable_pets_img("Screenshot_20250308-073732.png", |img| {
-            assert!(!img.margin_is_on_left);
+            assert!(!img.margin_is_on_left());
         });
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn can_detect_heart_count() {
+        let layout = LayoutProfile::default();
         get_probable_pets_img("Screenshot_20240714-090957.png", |img| {
-            let petsimg = PetsImage::new(&img).expect("it should be a petsimg");
+            let petsimg = PetsImage::new(&img, &layout);
             assert_eq!(petsimg.heart_data.num_hearts, 3);
-            assert_eq!(petsimg.heart_data.valid_red_color, [232, 52, 37, 255]);
-            assert_eq!(petsimg.heart_data.has_bandage, false);
+            assert_eq!(petsimg.heart_data.valid_red_color, Some([232, 52, 37, 255]));
+            assert_eq!(petsimg.heart_data.has_bandage(), false);
         });
 
         get_probable_pets_img("Screenshot_20250308-073732.png", |img| {
-            let petsimg = PetsImage::new(&img).expect("it should be a petsimg");
+            let petsimg = PetsImage::new(&img, &layout);
             assert_eq!(petsimg.heart_data.num_hearts, 2);
-            assert_eq!(petsimg.heart_data.valid_red_color, [253, 6, 6, 255]);
-            assert_eq!(petsimg.heart_data.has_bandage, false);
+            assert_eq!(petsimg.heart_data.valid_red_color, Some([253, 6, 6, 255]));
+            assert_eq!(petsimg.heart_data.has_bandage(), false);
         });
 
         get_probable_pets_img("Screenshot_20240629-104250.png", |img| {
-            let petsimg = PetsImage::new(&img).expect("it should be a petsimg");
+            let petsimg = PetsImage::new(&img, &layout);
             assert_eq!(petsimg.heart_data.num_hearts, 2);
-            assert_eq!(petsimg.heart_data.has_bandage, true);
+            assert_eq!(petsimg.heart_data.has_bandage(), true);
         });
     }
+
+    #[test]
+    fn can_extract_at_multiple_resolutions() {
+        let layout = LayoutProfile::default();
+        // (width, height, left margin, right margin)
+        let resolutions = [
+            (2400, 1080, 131, 0),
+            (2340, 1080, 0, 131),
+            (2772, 1240, 151, 0),
+            (1920, 1080, 0, 0),
+        ];
+        for (width, height, margin_left, margin_right) in resolutions {
+            let synth = SynthScreenshot {
+                margin_left,
+                margin_right,
+                num_hearts: 4,
+                has_bandage: true,
+                turn_number: 27,
+                ..SynthScreenshot::new(width, height)
+            };
+            let img = synth.render();
+            let probpets = probable_pets_image_check(&img).expect("it should be a pets img");
+            assert_eq!(probpets.left_margin, margin_left, "{width}x{height}");
+            assert_eq!(probpets.right_margin, margin_right, "{width}x{height}");
+            let petsimg = PetsImage::new(&probpets, &layout);
+            assert_eq!(petsimg.heart_data.num_hearts, 4, "{width}x{height}");
+            assert!(petsimg.heart_data.has_bandage(), "{width}x{height}");
+            assert_eq!(petsimg.get_turn_number().value(), Some(27), "{width}x{height}");
+        }
+    }
+
+    #[test]
+    fn can_count_full_empty_and_lost_hearts() {
+        let layout = LayoutProfile::default();
+        // hearts drawn in a shifted red, closer together than on the reference screenshot
+        for (width, height, margin) in [(2400, 1080, 131), (2772, 1240, 0), (1920, 1080, 0)] {
+            let img = SynthScreenshot {
+                num_hearts: 2,
+                lost_hearts: 1,
+                empty_hearts: 2,
+                heart_spacing: 0.07,
+                red: [240, 18, 12, 255],
+                has_bandage: true,
+                margin_left: margin,
+                ..SynthScreenshot::new(width, height)
+            }.render();
+            let probpets = probable_pets_image_check(&img).expect("it should be a pets img");
+            let petsimg = PetsImage::new(&probpets, &layout);
+            let hearts = &petsimg.heart_data;
+            assert_eq!((hearts.num_hearts, hearts.lost_hearts, hearts.empty_hearts), (2, 1, 2), "{width}x{height}");
+            assert_eq!(hearts.valid_red_color, Some([253, 6, 6, 255]));
+            assert!(hearts.has_bandage());
+        }
+    }
+
+    #[test]
+    fn can_count_zero_to_max_hearts() {
+        // no hearts left is zero hearts, not an error
+        let no_hearts = SynthScreenshot { num_hearts: 0, ..SynthScreenshot::new(2400, 1080) }.render();
+        let probpets = probable_pets_image_check(&no_hearts).expect("it should be a pets img");
+        let default_layout = LayoutProfile::default();
+        let petsimg = PetsImage::new(&probpets, &default_layout);
+        assert_eq!(petsimg.heart_data.num_hearts, 0);
+        assert!(!petsimg.heart_data.has_bandage());
+        let mut bytes = vec![];
+        no_hearts.write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png).expect("png encodes");
+        assert_eq!(get_image_data(&bytes).map(|data| data.heart_data.num_hearts).ok(), Some(0));
+
+        // a custom lobby with more lives, and a profile whose heart row fits them all
+        let layout = LayoutProfile {
+            heart_view: NormRect { width: 0.65, ..LayoutProfile::default().heart_view },
+            max_hearts: 8,
+            ..LayoutProfile::default()
+        };
+        for num_hearts in [1, 5, 6, 8] {
+            let img = SynthScreenshot { num_hearts, heart_spacing: 0.07, layout: layout.clone(), ..SynthScreenshot::new(2400, 1080) }.render();
+            let probpets = probable_pets_image_check(&img).expect("it should be a pets img");
+            assert_eq!(PetsImage::new(&probpets, &layout).heart_data.num_hearts, num_hearts);
+        }
+        let too_many = SynthScreenshot { num_hearts: 8, heart_spacing: 0.07, layout: layout.clone(), ..SynthScreenshot::new(2400, 1080) }.render();
+        let probpets = probable_pets_image_check(&too_many).expect("it should be a pets img");
+        let fewer_lives = LayoutProfile { max_hearts: 6, ..layout };
+        assert_eq!(PetsImage::new(&probpets, &fewer_lives).heart_data.num_hearts, 6);
+    }
+
+    #[test]
+    fn can_detect_heart_decorations() {
+        let layout = LayoutProfile::default();
+        // references from one labelled screenshot: a bandage over the first heart
+        let labelled = SynthScreenshot { num_hearts: 3, has_bandage: true, ..SynthScreenshot::new(2400, 1080) }.render();
+        let probpets = probable_pets_image_check(&labelled).expect("it should be a pets img");
+        let vectors = PetsImage::new(&probpets, &layout).get_heart_slot_vectors();
+        assert_eq!(vectors.len(), 3);
+        let references: Vec<(&'static str, icon::IconVector)> = [decoration::BANDAGE, decoration::PLAIN_HEART, decoration::PLAIN_HEART].into_iter().zip(vectors).collect();
+
+        for (width, height, margin) in [(2772, 1240, 0), (1920, 1080, 0)] {
+            let img = SynthScreenshot { num_hearts: 4, has_bandage: true, margin_left: margin, ..SynthScreenshot::new(width, height) }.render();
+            let probpets = probable_pets_image_check(&img).expect("it should be a pets img");
+            let petsimg = PetsImage::new(&probpets, &layout);
+            let found = decoration::detect_decorations_with(&petsimg.get_heart_slot_views(), &references);
+            assert_eq!(found.iter().map(|d| (d.slot, d.name)).collect::<Vec<_>>(), vec![(0, decoration::BANDAGE)], "{width}x{height}");
+            assert!(found[0].confidence > 0.0);
+        }
+
+        // something dark drawn where the bandage goes is not a bandage, even though the bandage probe says it is
+        let mut img = SynthScreenshot { num_hearts: 2, ..SynthScreenshot::new(2400, 1080) }.render();
+        let (x, y) = testutil::bandage_point(&layout).to_pixels(2400, 1080);
+        image::imageops::replace(&mut img, &image::RgbaImage::from_pixel(30, 30, image::Rgba([30, 30, 30, 255])), x as i64 - 15, y as i64 - 15);
+        let probpets = probable_pets_image_check(&img).expect("it should be a pets img");
+        let petsimg = PetsImage::new(&probpets, &layout);
+        assert!(petsimg.heart_data.has_bandage());
+        assert_eq!(decoration::detect_decorations_with(&petsimg.get_heart_slot_views(), &references), vec![]);
+    }
+
+    #[test]
+    fn can_measure_margins() {
+        // (left margin, right margin)
+        let margins = [(131, 0), (0, 131), (90, 0), (60, 75), (0, 0)];
+        for (margin_left, margin_right) in margins {
+            let img = SynthScreenshot { margin_left, margin_right, ..SynthScreenshot::new(2400, 1080) }.render();
+            let probpets = probable_pets_image_check(&img).expect("it should be a pets img");
+            assert_eq!(probpets.left_margin, margin_left);
+            assert_eq!(probpets.right_margin, margin_right);
+            assert_eq!(probpets.view.width(), 2400 - margin_left - margin_right);
+            assert_eq!(probpets.margin_is_on_left(), margin_left >= margin_right);
+        }
+    }
+
+    #[test]
+    fn rejects_black_images() {
+        let img = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(2400, 1080, image::Rgba(BLACK)));
+        assert_eq!(probable_pets_image_check(&img).err(), Some(ExtractError::MissingMargin));
+    }
+
+    #[test]
+    fn rejects_portrait_images() {
+        let img = SynthScreenshot::new(1080, 2400).render();
+        assert_eq!(probable_pets_image_check(&img).err(), Some(ExtractError::WrongDimensions { width: 1080, height: 2400 }));
+    }
+
+    #[test]
+    fn reports_why_extraction_failed() {
+        let encode = |img: DynamicImage| {
+            let mut bytes = vec![];
+            img.write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png).expect("png encodes");
+            bytes
+        };
+        assert!(matches!(get_image_data(b"not a png"), Err(ExtractError::Decode(_))));
+        let mut no_digits = SynthScreenshot::new(2400, 1080).render();
+        let layout = LayoutProfile::default();
+        let (x, y, w, h) = layout.turn_number_view.to_pixels(2400, 1080);
+        image::imageops::replace(&mut no_digits, &image::RgbaImage::from_pixel(w, h, image::Rgba(BLACK)), x as i64, y as i64);
+        assert_eq!(get_image_data(&encode(no_digits)).err(), Some(ExtractError::DigitSegmentation));
+    }
+
+    #[test]
+    fn can_classify_outcome() {
+        let layout = LayoutProfile::default();
+        for outcome in [Outcome::Win, Outcome::Loss, Outcome::Draw, Outcome::Unknown] {
+            for (width, height, margin) in [(2400, 1080, 131), (1920, 1080, 0)] {
+                let img = SynthScreenshot { outcome, margin_left: margin, ..SynthScreenshot::new(width, height) }.render();
+                let probpets = probable_pets_image_check(&img).expect("it should be a pets img");
+                let petsimg = PetsImage::new(&probpets, &layout);
+                assert_eq!(petsimg.get_outcome(), outcome);
+            }
+        }
+    }
+
+    #[test]
+    fn can_read_team_levels() {
+        let layout = LayoutProfile::default();
+        let team_levels = vec![(1, 0), (1, 1), (2, 2), (3, 0), (2, 0)];
+        for (width, height, margin) in [(2400, 1080, 131), (2772, 1240, 0)] {
+            let img = SynthScreenshot { team_levels: team_levels.clone(), margin_left: margin, ..SynthScreenshot::new(width, height) }.render();
+            let probpets = probable_pets_image_check(&img).expect("it should be a pets img");
+            let petsimg = PetsImage::new(&probpets, &layout);
+            let expected: Vec<_> = team_levels.iter().map(|(level, experience)| PetLevel::Known { level: *level, experience: *experience }).collect();
+            assert_eq!(petsimg.get_team_levels(), expected);
+        }
+
+        // something drawn over the experience bar of the first pet
+        let mut img = SynthScreenshot { team_levels, ..SynthScreenshot::new(2400, 1080) }.render();
+        let (x, y, w, h) = layout.level_views[0].experience.to_pixels(2400, 1080);
+        image::imageops::replace(&mut img, &image::RgbaImage::from_pixel(w / 2, h, image::Rgba([230, 230, 230, 255])), x as i64, y as i64);
+        let probpets = probable_pets_image_check(&img).expect("it should be a pets img");
+        let petsimg = PetsImage::new(&probpets, &layout);
+        assert_eq!(petsimg.get_team_levels()[0], PetLevel::Unknown);
+        assert_eq!(petsimg.get_team_levels()[1], PetLevel::Known { level: 1, experience: 1 });
+    }
+
+    #[test]
+    fn can_classify_scenes() {
+        // signatures of the stand-in UI elements `SynthScreenshot` draws, not the unmeasured
+        // ones of the default profile: this only tests the classifier, not the profile
+        let marker = |x, y, width, height, color| scene::UiMarker { region: NormRect::from_reference(x, y, width, height), color };
+        let layout = LayoutProfile {
+            scene_signatures: vec![
+                scene::SceneSignature { scene: Scene::EndScreen, markers: vec![marker(800, 80, 600, 100, [145, 145, 155, 255])] },
+                scene::SceneSignature { scene: Scene::Shop, markers: vec![
+                    marker(60, 960, 200, 80, testutil::ROLL_BUTTON),
+                    marker(2000, 960, 200, 80, testutil::END_TURN_BUTTON),
+                ] },
+                scene::SceneSignature { scene: Scene::Battle, markers: vec![
+                    marker(50, 960, 90, 80, testutil::BATTLE_BUTTON),
+                    marker(190, 960, 90, 80, testutil::BATTLE_BUTTON),
+                ] },
+                scene::SceneSignature { scene: Scene::MainMenu, markers: vec![
+                    marker(860, 140, 550, 180, testutil::MENU_LOGO),
+                    marker(860, 720, 550, 120, testutil::END_TURN_BUTTON),
+                ] },
+            ],
+            ..LayoutProfile::default()
+        };
+        for (width, height, margin) in [(2400, 1080, 131), (2772, 1240, 0), (1920, 1080, 0)] {
+            let screens = [
+                (SynthScreenshot { outcome: Outcome::Loss, ..SynthScreenshot::new(width, height) }, Scene::EndScreen),
+                (SynthScreenshot { scene: Some(Scene::Shop), ..SynthScreenshot::new(width, height) }, Scene::Shop),
+                (SynthScreenshot { scene: Some(Scene::Battle), ..SynthScreenshot::new(width, height) }, Scene::Battle),
+                (SynthScreenshot { scene: Some(Scene::MainMenu), ..SynthScreenshot::new(width, height) }, Scene::MainMenu),
+                (SynthScreenshot::new(width, height), Scene::Unrelated),
+            ];
+            for (screen, expected) in screens {
+                let img = SynthScreenshot { margin_left: margin, ..screen }.render();
+                let probpets = probable_pets_image_check(&img).expect("it should be a pets img");
+                let reading = PetsImage::new(&probpets, &layout).get_scene();
+                assert_eq!(reading.scene, expected, "{width}x{height}");
+                assert!(reading.confidence() >= 0.5);
+            }
+        }
+    }
+
+    #[test]
+    fn digit_confidence_is_margin_to_runner_up() {
+        // an exact template match is confident
+        let reading = read_digit(&VEC_7);
+        assert_eq!(reading.digit, 7);
+        assert!(reading.confidence > 1.0);
+
+        // a blend of two templates is ambiguous
+        let mut blend = VEC_3;
+        for (i, px) in VEC_8.iter().enumerate() {
+            if i % 2 == 0 {
+                blend[i] = *px;
+            }
+        }
+        assert!(read_digit(&blend).confidence < reading.confidence);
+    }
+
+    #[test]
+    fn unreadable_turn_number_is_explicit() {
+        let layout = LayoutProfile::default();
+        let mut img = SynthScreenshot::new(2400, 1080).render();
+        let (x, y, w, h) = layout.turn_number_view.to_pixels(2400, 1080);
+        image::imageops::replace(&mut img, &image::RgbaImage::from_pixel(w, h, image::Rgba(BLACK)), x as i64, y as i64);
+        let probpets = probable_pets_image_check(&img).expect("it should be a pets img");
+        let petsimg = PetsImage::new(&probpets, &layout);
+        assert_eq!(petsimg.get_turn_number(), TurnNumber::Unreadable);
+        assert_eq!(petsimg.get_turn_number().confidence(), 0.0);
+
+        let img = SynthScreenshot { turn_number: 18, ..SynthScreenshot::new(2400, 1080) }.render();
+        let probpets = probable_pets_image_check(&img).expect("it should be a pets img");
+        let petsimg = PetsImage::new(&probpets, &layout);
+        let TurnNumber::Read { value, digits } = petsimg.get_turn_number() else {
+            panic!("it should be readable");
+        };
+        assert_eq!(value, 18);
+        assert_eq!(digits.iter().map(|d| d.digit).collect::<Vec<_>>(), vec![1, 8]);
+        assert!(digits.iter().all(|d| d.confidence > 0.0));
+    }
+
+    #[test]
+    fn reads_near_white_digits_on_noisy_box() {
+        let layout = LayoutProfile::default();
+        for turn_number in [10, 37, 84] {
+            let mut img = SynthScreenshot {
+                turn_number,
+                digit_color: [236, 242, 229, 255],
+                turn_box_color: [96, 74, 58, 255],
+                ..SynthScreenshot::new(2400, 1080)
+            }.render().to_rgba8();
+            // recompression style noise, so no pixel is exactly the color it was drawn with
+            let (x, y, w, h) = layout.turn_number_view.to_pixels(2400, 1080);
+            for py in y..y + h {
+                for px in x..x + w {
+                    let noise = ((px * 7 + py * 13) % 17) as u8;
+                    img.get_pixel_mut(px, py).0[..3].iter_mut().for_each(|c| *c = c.saturating_sub(noise));
+                }
+            }
+            let img = DynamicImage::ImageRgba8(img);
+            let probpets = probable_pets_image_check(&img).expect("it should be a pets img");
+            let petsimg = PetsImage::new(&probpets, &layout);
+            assert_eq!(petsimg.get_turn_number().value(), Some(turn_number));
+        }
+    }
+
+    #[test]
+    fn nearest_neighbours_tolerate_shifted_digits() {
+        // shift every digit one pixel to the right, as a slightly different crop would
+        for (digit, template) in [VEC_0, VEC_1, VEC_2, VEC_3, VEC_4, VEC_5, VEC_6, VEC_7, VEC_8, VEC_9].iter().enumerate() {
+            let mut shifted = [0; 256];
+            for y in 0..16 {
+                for x in 1..16 {
+                    shifted[y * 16 + x] = template[y * 16 + x - 1];
+                }
+            }
+            assert_eq!(get_digit(&shifted), digit as u8);
+        }
+    }
+
+    #[test]
+    fn every_digit_has_reference_samples() {
+        // at least its template, even while nobody has labelled any crops of it
+        for digit in 0..10 {
+            assert!(DIGIT_SAMPLES.iter().any(|(d, _)| *d == digit), "digit {digit} has no samples");
+        }
+    }
 }
@@ -0,0 +1,115 @@
+//! read a number with any amount of digits out of a region of the screen.
+//! the region is binarized, split into glyphs by scanning for empty columns
+//! (see `extract_digit_zone`) and each glyph is classified against the
+//! reference samples of the font the number is drawn in
+
+use image::{imageops::FilterType::Nearest, DynamicImage, GenericImageView, SubImage};
+
+use crate::*;
+
+/// the font a number is drawn in, as a set of (digit, 16x16 sample) references
+#[derive(Debug, Clone, Copy)]
+pub struct NumberStyle {
+    pub samples: &'static [(u8, [u8; 256])],
+}
+
+/// the white outlined digits of the turn counter
+pub const TURN_COUNTER: NumberStyle = NumberStyle { samples: DIGIT_SAMPLES };
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberReading {
+    pub value: u32,
+    /// each digit of the value, left to right
+    pub digits: Vec<DigitReading>,
+}
+
+impl NumberReading {
+    /// confidence of the least certain digit
+    pub fn confidence(&self) -> f64 {
+        self.digits.iter().map(|d| d.confidence).fold(f64::MAX, f64::min)
+    }
+}
+
+/// read the number shown in the view. `None` if there are no glyphs in it,
+/// or more than fit in a u32
+pub fn read_number(view: &SubImage<&DynamicImage>, style: &NumberStyle) -> Option<NumberReading> {
+    let digits: Vec<DigitReading> = get_glyph_vectors(view).iter()
+        .map(|glyph| read_digit_with_samples(glyph, style.samples))
+        .collect();
+    if digits.is_empty() {
+        return None;
+    }
+    let value = digits.iter().try_fold(0u32, |value, d| value.checked_mul(10)?.checked_add(d.digit as u32))?;
+    Some(NumberReading { value, digits })
+}
+
+/// binarize the view and clip out every glyph in it, left to right, as 16x16 vectors.
+/// specks much shorter than the tallest glyph are dropped
+pub fn get_glyph_vectors(view: &SubImage<&DynamicImage>) -> Vec<[u8; 256]> {
+    let mask = threshold::binarize(view);
+    let mask_view = mask.view(0, 0, mask.width(), mask.height());
+    let mut glyphs = vec![];
+    let mut start_x = 0;
+    while let Some((end_x, glyph)) = extract_digit_zone(&mask_view, start_x) {
+        glyphs.push((glyph_height(&glyph), glyph_vector(&glyph)));
+        start_x = end_x;
+    }
+    let tallest = glyphs.iter().map(|(height, _)| *height).max().unwrap_or(0);
+    glyphs.into_iter()
+        .filter(|(height, _)| *height * 2 >= tallest)
+        .map(|(_, vector)| vector)
+        .collect()
+}
+
+/// rows between the topmost and bottommost set pixel
+fn glyph_height(glyph: &SubImage<&DynamicImage>) -> u32 {
+    let rows: Vec<u32> = glyph.pixels().filter(|(_, _, px)| px.0 == WHITE).map(|(_, y, _)| y).collect();
+    match (rows.iter().min(), rows.iter().max()) {
+        (Some(top), Some(bottom)) => bottom - top + 1,
+        _ => 0,
+    }
+}
+
+fn glyph_vector(glyph: &SubImage<&DynamicImage>) -> [u8; 256] {
+    let scaled = image::imageops::resize(&glyph.to_image(), 16, 16, Nearest);
+    let mut vector = [0; 256];
+    for (i, px) in scaled.pixels().enumerate() {
+        if px.0 == WHITE {
+            vector[i] = 1;
+        }
+    }
+    vector
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testutil::render_number;
+
+    #[test]
+    fn reads_any_number_of_digits() {
+        for value in [0, 7, 42, 100, 365, 2024] {
+            let img = render_number(value, 28, 66, 4);
+            let view = img.view(0, 0, img.width(), img.height());
+            let reading = read_number(&view, &TURN_COUNTER).expect("it should read a number");
+            assert_eq!(reading.value, value);
+            assert_eq!(reading.digits.len(), value.to_string().len());
+            assert!(reading.confidence() > 0.0);
+        }
+    }
+
+    #[test]
+    fn empty_view_has_no_number() {
+        let img = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(40, 20, image::Rgba(BLACK)));
+        assert_eq!(read_number(&img.view(0, 0, 40, 20), &TURN_COUNTER), None);
+    }
+
+    #[test]
+    fn specks_are_not_glyphs() {
+        let mut img = render_number(5, 28, 66, 4).to_rgba8();
+        img.put_pixel(img.width() - 2, 30, image::Rgba(WHITE));
+        let img = DynamicImage::ImageRgba8(img);
+        let reading = read_number(&img.view(0, 0, img.width(), img.height()), &TURN_COUNTER).expect("it should read a number");
+        assert_eq!(reading.value, 5);
+    }
+}
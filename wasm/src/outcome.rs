@@ -0,0 +1,77 @@
+//! tell win, loss and draw end screens apart by the color of the banner
+//! announcing the result
+
+use image::{DynamicImage, SubImage};
+use serde::{Deserialize, Serialize};
+
+use crate::color::color_coverage;
+
+/// how the game on the screenshot ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+    /// no banner color covers enough of the banner region
+    Unknown,
+}
+
+impl Outcome {
+    /// stable code sent across the wasm boundary in the extraction record
+    pub fn code(&self) -> i32 {
+        match self {
+            Outcome::Unknown => 0,
+            Outcome::Win => 1,
+            Outcome::Loss => 2,
+            Outcome::Draw => 3,
+        }
+    }
+}
+
+/// a color the result banner is drawn in for one outcome
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OutcomeColor {
+    pub outcome: Outcome,
+    pub color: [u8; 4],
+}
+
+/// the winning banner color has to cover at least this fraction of the banner region
+const MIN_BANNER_COVERAGE: f64 = 0.25;
+
+/// the outcome whose banner color covers the most of the view
+pub fn classify_outcome(view: &SubImage<&DynamicImage>, colors: &[OutcomeColor]) -> Outcome {
+    let best = colors.iter()
+        .map(|c| (c.outcome, color_coverage(view, c.color)))
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+    match best {
+        Some((outcome, coverage)) if coverage >= MIN_BANNER_COVERAGE => outcome,
+        _ => Outcome::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::{GenericImageView, RgbaImage};
+
+    const COLORS: [OutcomeColor; 2] = [
+        OutcomeColor { outcome: Outcome::Win, color: [250, 200, 30, 255] },
+        OutcomeColor { outcome: Outcome::Loss, color: [120, 120, 130, 255] },
+    ];
+
+    #[test]
+    fn picks_the_dominant_banner_color() {
+        // mostly a slightly off loss banner, with some win colored text on it
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(10, 10, |x, _| {
+            image::Rgba(if x < 3 { [250, 200, 30, 255] } else { [128, 114, 136, 255] })
+        }));
+        assert_eq!(classify_outcome(&img.view(0, 0, 10, 10), &COLORS), Outcome::Loss);
+    }
+
+    #[test]
+    fn no_banner_is_unknown() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(10, 10, image::Rgba([40, 120, 200, 255])));
+        assert_eq!(classify_outcome(&img.view(0, 0, 10, 10), &COLORS), Outcome::Unknown);
+    }
+}
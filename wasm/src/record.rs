@@ -0,0 +1,255 @@
+//! the result of extracting a screenshot, in the form it is handed to javascript.
+//! the record is a flat list of little endian i32 words written into wasm memory.
+//! the first word is always the format version, so the frontend can tell which
+//! fields follow. the format has not been released yet, so it is still version 1
+//! and its fields are packed without gaps. once it is released, new fields are
+//! only ever appended, and doing so bumps the version
+
+use crate::{ExtractError, HeartDecoration, ImageData, PetLevel};
+
+pub const RECORD_FORMAT_VERSION: i32 = 1;
+
+/// how many team slots the record has room for
+pub const TEAM_SIZE: usize = 5;
+
+/// how many heart slot decorations the record has room for
+pub const MAX_DECORATIONS: usize = 4;
+
+/// how many turn number digits the record has room for. turn numbers fit in a `u8`
+pub const MAX_TURN_DIGITS: usize = 3;
+
+/// word offsets of each field within the record
+pub mod field {
+    pub const VERSION: usize = 0;
+    /// 0 if the screenshot was extracted, otherwise the `ExtractError::code`
+    /// of the stage that rejected it
+    pub const STATUS: usize = 1;
+    pub const NUM_HEARTS: usize = 2;
+    /// 1 if there is a bandage on the hearts, 0 otherwise. see `HEART_DECORATION_IDS` for any other icon
+    pub const HAS_BANDAGE: usize = 3;
+    pub const TURN_NUMBER: usize = 4;
+    /// `DigitReading::confidence` of the least certain turn digit, in thousandths.
+    /// see `TURN_DIGIT_CONFIDENCE` for every digit
+    pub const TURN_CONFIDENCE: usize = 5;
+    /// `Outcome::code`: 0 unknown, 1 win, 2 loss, 3 draw
+    pub const OUTCOME: usize = 6;
+    /// level (1 to 3) of each team pet, 0 if unknown. `TEAM_SIZE` words
+    pub const TEAM_LEVEL: usize = 7;
+    /// filled experience pips of each team pet, -1 if unknown. `TEAM_SIZE` words
+    pub const TEAM_EXPERIENCE: usize = TEAM_LEVEL + super::TEAM_SIZE;
+    /// hearts of lives lost in earlier turns
+    pub const EMPTY_HEARTS: usize = TEAM_EXPERIENCE + super::TEAM_SIZE;
+    /// hearts of lives lost this turn
+    pub const LOST_HEARTS: usize = EMPTY_HEARTS + 1;
+    /// heart slot (counting from 0) of each decoration on the hearts, -1 for unused words. `MAX_DECORATIONS` words
+    pub const HEART_DECORATION_SLOTS: usize = LOST_HEARTS + 1;
+    /// `HeartDecoration::id` of each decoration, 0 for unused words. `MAX_DECORATIONS` words
+    pub const HEART_DECORATION_IDS: usize = HEART_DECORATION_SLOTS + super::MAX_DECORATIONS;
+    /// `HeartDecoration::confidence` of each decoration in thousandths. `MAX_DECORATIONS` words
+    pub const HEART_DECORATION_CONFIDENCE: usize = HEART_DECORATION_IDS + super::MAX_DECORATIONS;
+    /// `DigitReading::confidence` of each turn digit in thousandths, left to right,
+    /// -1 for unused words. `MAX_TURN_DIGITS` words
+    pub const TURN_DIGIT_CONFIDENCE: usize = HEART_DECORATION_CONFIDENCE + super::MAX_DECORATIONS;
+    /// `Scene::code`: 0 unrelated, 1 end screen, 2 shop, 3 battle, 4 main menu.
+    /// 0 for screenshots that could not be extracted
+    pub const SCENE: usize = TURN_DIGIT_CONFIDENCE + super::MAX_TURN_DIGITS;
+    /// `SceneReading::confidence` of that scene, in thousandths
+    pub const SCENE_CONFIDENCE: usize = SCENE + 1;
+    /// total number of words in the record
+    pub const COUNT: usize = SCENE_CONFIDENCE + 1;
+}
+
+pub const STATUS_OK: i32 = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ExtractionRecord {
+    pub status: i32,
+    pub num_hearts: i32,
+    pub has_bandage: i32,
+    pub turn_number: i32,
+    pub turn_confidence: i32,
+    pub outcome: i32,
+    pub team_level: [i32; TEAM_SIZE],
+    pub team_experience: [i32; TEAM_SIZE],
+    pub empty_hearts: i32,
+    pub lost_hearts: i32,
+    pub heart_decoration_slots: [i32; MAX_DECORATIONS],
+    pub heart_decoration_ids: [i32; MAX_DECORATIONS],
+    pub heart_decoration_confidence: [i32; MAX_DECORATIONS],
+    pub turn_digit_confidence: [i32; MAX_TURN_DIGITS],
+    pub scene: i32,
+    pub scene_confidence: i32,
+}
+
+impl ExtractionRecord {
+    pub fn from_result(result: &Result<ImageData, ExtractError>) -> Self {
+        match result {
+            Ok(data) => {
+                let mut team_level = [0; TEAM_SIZE];
+                let mut team_experience = [-1; TEAM_SIZE];
+                for (i, level) in data.team_levels.iter().take(TEAM_SIZE).enumerate() {
+                    if let PetLevel::Known { level, experience } = level {
+                        team_level[i] = *level as i32;
+                        team_experience[i] = *experience as i32;
+                    }
+                }
+                let mut turn_digit_confidence = [-1; MAX_TURN_DIGITS];
+                for (i, digit) in data.turn_digits.iter().take(MAX_TURN_DIGITS).enumerate() {
+                    turn_digit_confidence[i] = to_thousandths(digit.confidence);
+                }
+                Self {
+                    status: STATUS_OK,
+                    num_hearts: data.heart_data.num_hearts as i32,
+                    has_bandage: data.heart_data.has_bandage() as i32,
+                    turn_number: data.turn_number as i32,
+                    turn_confidence: to_thousandths(data.turn_digits.iter().map(|d| d.confidence).fold(f64::MAX, f64::min)),
+                    outcome: data.outcome.code(),
+                    team_level,
+                    team_experience,
+                    empty_hearts: data.heart_data.empty_hearts as i32,
+                    lost_hearts: data.heart_data.lost_hearts as i32,
+                    heart_decoration_slots: decoration_words(&data.heart_data.decorations, -1, |d| d.slot as i32),
+                    heart_decoration_ids: decoration_words(&data.heart_data.decorations, 0, HeartDecoration::id),
+                    heart_decoration_confidence: decoration_words(&data.heart_data.decorations, 0, |d| to_thousandths(d.confidence)),
+                    turn_digit_confidence,
+                    scene: data.scene.scene.code(),
+                    scene_confidence: to_thousandths(data.scene.confidence()),
+                }
+            }
+            Err(e) => Self { status: e.code(), turn_digit_confidence: [-1; MAX_TURN_DIGITS], ..Default::default() },
+        }
+    }
+
+    pub fn to_words(&self) -> [i32; field::COUNT] {
+        let mut words = [0; field::COUNT];
+        words[field::VERSION] = RECORD_FORMAT_VERSION;
+        words[field::STATUS] = self.status;
+        words[field::NUM_HEARTS] = self.num_hearts;
+        words[field::HAS_BANDAGE] = self.has_bandage;
+        words[field::TURN_NUMBER] = self.turn_number;
+        words[field::TURN_CONFIDENCE] = self.turn_confidence;
+        words[field::OUTCOME] = self.outcome;
+        words[field::TEAM_LEVEL..field::TEAM_LEVEL + TEAM_SIZE].copy_from_slice(&self.team_level);
+        words[field::TEAM_EXPERIENCE..field::TEAM_EXPERIENCE + TEAM_SIZE].copy_from_slice(&self.team_experience);
+        words[field::EMPTY_HEARTS] = self.empty_hearts;
+        words[field::LOST_HEARTS] = self.lost_hearts;
+        words[field::HEART_DECORATION_SLOTS..field::HEART_DECORATION_SLOTS + MAX_DECORATIONS].copy_from_slice(&self.heart_decoration_slots);
+        words[field::HEART_DECORATION_IDS..field::HEART_DECORATION_IDS + MAX_DECORATIONS].copy_from_slice(&self.heart_decoration_ids);
+        words[field::HEART_DECORATION_CONFIDENCE..field::HEART_DECORATION_CONFIDENCE + MAX_DECORATIONS].copy_from_slice(&self.heart_decoration_confidence);
+        words[field::TURN_DIGIT_CONFIDENCE..field::TURN_DIGIT_CONFIDENCE + MAX_TURN_DIGITS].copy_from_slice(&self.turn_digit_confidence);
+        words[field::SCENE] = self.scene;
+        words[field::SCENE_CONFIDENCE] = self.scene_confidence;
+        words
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_words().iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    /// parse a record of the current format version. mostly useful for tests
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != field::COUNT * 4 {
+            return None;
+        }
+        let words: Vec<i32> = bytes.chunks_exact(4)
+            .map(|c| i32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect();
+        if words[field::VERSION] != RECORD_FORMAT_VERSION {
+            return None;
+        }
+        let team_words = |start: usize| {
+            let mut team = [0; TEAM_SIZE];
+            team.copy_from_slice(&words[start..start + TEAM_SIZE]);
+            team
+        };
+        let decoration_slice = |start: usize| {
+            let mut decorations = [0; MAX_DECORATIONS];
+            decorations.copy_from_slice(&words[start..start + MAX_DECORATIONS]);
+            decorations
+        };
+        Some(Self {
+            status: words[field::STATUS],
+            num_hearts: words[field::NUM_HEARTS],
+            has_bandage: words[field::HAS_BANDAGE],
+            turn_number: words[field::TURN_NUMBER],
+            turn_confidence: words[field::TURN_CONFIDENCE],
+            outcome: words[field::OUTCOME],
+            team_level: team_words(field::TEAM_LEVEL),
+            team_experience: team_words(field::TEAM_EXPERIENCE),
+            empty_hearts: words[field::EMPTY_HEARTS],
+            lost_hearts: words[field::LOST_HEARTS],
+            heart_decoration_slots: decoration_slice(field::HEART_DECORATION_SLOTS),
+            heart_decoration_ids: decoration_slice(field::HEART_DECORATION_IDS),
+            heart_decoration_confidence: decoration_slice(field::HEART_DECORATION_CONFIDENCE),
+            turn_digit_confidence: std::array::from_fn(|i| words[field::TURN_DIGIT_CONFIDENCE + i]),
+            scene: words[field::SCENE],
+            scene_confidence: words[field::SCENE_CONFIDENCE],
+        })
+    }
+}
+
+/// one word per decoration, `unused` for the words past the last one
+fn decoration_words(decorations: &[HeartDecoration], unused: i32, word: impl Fn(&HeartDecoration) -> i32) -> [i32; MAX_DECORATIONS] {
+    let mut words = [unused; MAX_DECORATIONS];
+    for (i, decoration) in decorations.iter().take(MAX_DECORATIONS).enumerate() {
+        words[i] = word(decoration);
+    }
+    words
+}
+
+fn to_thousandths(value: f64) -> i32 {
+    if value.is_finite() { (value * 1000.0).round() as i32 } else { 0 }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{DigitReading, HeartData, Outcome, Scene, SceneReading};
+
+    #[test]
+    fn record_keeps_full_width_turn_numbers() {
+        for turn_number in [1, 9, 10, 24, 30, 45, 99] {
+            let data = ImageData {
+                heart_data: HeartData {
+                    valid_red_color: Some([253, 6, 6, 255]),
+                    num_hearts: 5,
+                    empty_hearts: 2,
+                    lost_hearts: 1,
+                    decorations: vec![HeartDecoration { slot: 0, name: crate::decoration::BANDAGE, confidence: 0.75 }],
+                },
+                outcome: Outcome::Loss,
+                team_levels: vec![PetLevel::Known { level: 2, experience: 1 }, PetLevel::Unknown],
+                turn_number,
+                turn_digits: vec![
+                    DigitReading { digit: turn_number / 10, confidence: 2.5 },
+                    DigitReading { digit: turn_number % 10, confidence: 0.25 },
+                ],
+                scene: SceneReading { scene: Scene::EndScreen, confidences: [0.25, 0.75, 0.0, 0.0, 0.0] },
+            };
+            let record = ExtractionRecord::from_result(&Ok(data));
+            let parsed = ExtractionRecord::from_bytes(&record.to_bytes()).expect("valid record");
+            assert_eq!(parsed.turn_number, turn_number as i32);
+            assert_eq!(parsed.num_hearts, 5);
+            assert_eq!(parsed.has_bandage, 1);
+            assert_eq!(parsed.status, STATUS_OK);
+            assert_eq!(parsed.turn_confidence, 250);
+            assert_eq!(parsed.outcome, Outcome::Loss.code());
+            assert_eq!(parsed.team_level, [2, 0, 0, 0, 0]);
+            assert_eq!(parsed.team_experience, [1, -1, -1, -1, -1]);
+            assert_eq!((parsed.empty_hearts, parsed.lost_hearts), (2, 1));
+            assert_eq!(parsed.heart_decoration_slots, [0, -1, -1, -1]);
+            assert_eq!(parsed.heart_decoration_ids, [1, 0, 0, 0]);
+            assert_eq!(parsed.heart_decoration_confidence, [750, 0, 0, 0]);
+            assert_eq!(parsed.turn_digit_confidence, [2500, 250, -1]);
+            assert_eq!((parsed.scene, parsed.scene_confidence), (Scene::EndScreen.code(), 750));
+        }
+    }
+
+    #[test]
+    fn failed_record_has_status_and_version() {
+        let record = ExtractionRecord::from_result(&Err(ExtractError::MissingMargin));
+        let words = record.to_words();
+        assert_eq!(words[field::VERSION], RECORD_FORMAT_VERSION);
+        assert_eq!(words[field::STATUS], ExtractError::MissingMargin.code());
+    }
+}
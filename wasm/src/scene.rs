@@ -0,0 +1,142 @@
+//! tell which screen of the game a screenshot shows, by the UI elements only
+//! that screen shows
+
+use image::{DynamicImage, GenericImageView, SubImage};
+use serde::{Deserialize, Serialize};
+
+use crate::color::color_coverage;
+use crate::NormRect;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scene {
+    /// the screen shown when a game is over. the only one we extract
+    EndScreen,
+    Shop,
+    Battle,
+    MainMenu,
+    /// not a screenshot of the game, or a screen none of the scene signatures match
+    Unrelated,
+}
+
+/// every scene, in the order of their codes
+pub const SCENES: [Scene; 5] = [Scene::Unrelated, Scene::EndScreen, Scene::Shop, Scene::Battle, Scene::MainMenu];
+
+impl Scene {
+    /// stable code sent across the wasm boundary
+    pub fn code(&self) -> i32 {
+        match self {
+            Scene::Unrelated => 0,
+            Scene::EndScreen => 1,
+            Scene::Shop => 2,
+            Scene::Battle => 3,
+            Scene::MainMenu => 4,
+        }
+    }
+}
+
+/// a UI element drawn in a flat color, eg: the shop's roll button
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct UiMarker {
+    pub region: NormRect,
+    pub color: [u8; 4],
+}
+
+/// the UI elements that are all present on one screen of the game. a scene
+/// can have several signatures, eg: one per color of the end screen banner
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneSignature {
+    pub scene: Scene,
+    pub markers: Vec<UiMarker>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneReading {
+    pub scene: Scene,
+    /// how sure we are of each scene, from 0 to 1, in the order of `SCENES`
+    pub confidences: [f64; SCENES.len()],
+}
+
+impl SceneReading {
+    pub fn confidence(&self) -> f64 {
+        self.confidence_of(self.scene)
+    }
+
+    pub fn confidence_of(&self, scene: Scene) -> f64 {
+        self.confidences[scene.code() as usize]
+    }
+}
+
+/// below this confidence a screenshot is rather unrelated than the scene
+const MIN_SCENE_CONFIDENCE: f64 = 0.5;
+
+/// the confidence of a scene is how well the best covered of its signatures is
+/// covered, where a signature is only as covered as its least covered marker.
+/// the confidence of unrelated is what is left over by the most likely scene
+pub fn classify_scene(viewport: &SubImage<&DynamicImage>, signatures: &[SceneSignature]) -> SceneReading {
+    let (width, height) = viewport.dimensions();
+    let marker_coverage = |marker: &UiMarker| {
+        let (x, y, w, h) = marker.region.to_pixels(width, height);
+        color_coverage(&viewport.view(x, y, w, h), marker.color)
+    };
+    let mut confidences = [0.0; SCENES.len()];
+    for signature in signatures.iter().filter(|s| !s.markers.is_empty() && s.scene != Scene::Unrelated) {
+        let coverage = signature.markers.iter().map(marker_coverage).fold(1.0, f64::min);
+        let confidence = &mut confidences[signature.scene.code() as usize];
+        *confidence = coverage.max(*confidence);
+    }
+    let (best, best_confidence) = SCENES.iter()
+        .map(|s| (*s, confidences[s.code() as usize]))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .expect("there is more than one scene");
+    confidences[Scene::Unrelated.code() as usize] = 1.0 - best_confidence;
+    let scene = if best_confidence >= MIN_SCENE_CONFIDENCE { best } else { Scene::Unrelated };
+    SceneReading { scene, confidences }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::NormRect;
+    use image::RgbaImage;
+
+    const LEFT: NormRect = NormRect { x: 0.0, y: 0.0, width: 0.5, height: 1.0 };
+    const RIGHT: NormRect = NormRect { x: 0.5, y: 0.0, width: 0.5, height: 1.0 };
+
+    fn signatures() -> Vec<SceneSignature> {
+        vec![
+            SceneSignature { scene: Scene::Shop, markers: vec![
+                UiMarker { region: LEFT, color: [80, 180, 60, 255] },
+                UiMarker { region: RIGHT, color: [245, 150, 30, 255] },
+            ] },
+            SceneSignature { scene: Scene::EndScreen, markers: vec![UiMarker { region: LEFT, color: [255, 196, 0, 255] }] },
+            SceneSignature { scene: Scene::EndScreen, markers: vec![UiMarker { region: LEFT, color: [145, 145, 155, 255] }] },
+        ]
+    }
+
+    fn screen(left: [u8; 4], right: [u8; 4]) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(20, 10, |x, _| image::Rgba(if x < 10 { left } else { right })))
+    }
+
+    #[test]
+    fn every_marker_of_a_signature_has_to_be_present() {
+        let shop = screen([80, 180, 60, 255], [245, 150, 30, 255]);
+        let reading = classify_scene(&shop.view(0, 0, 20, 10), &signatures());
+        assert_eq!(reading.scene, Scene::Shop);
+        assert_eq!(reading.confidence(), 1.0);
+        assert_eq!(reading.confidence_of(Scene::Unrelated), 0.0);
+
+        let half_a_shop = screen([80, 180, 60, 255], [40, 120, 200, 255]);
+        let reading = classify_scene(&half_a_shop.view(0, 0, 20, 10), &signatures());
+        assert_eq!(reading.scene, Scene::Unrelated);
+        assert_eq!(reading.confidence(), 1.0);
+    }
+
+    #[test]
+    fn any_signature_of_a_scene_can_match() {
+        let loss = screen([145, 145, 155, 255], [40, 120, 200, 255]);
+        let reading = classify_scene(&loss.view(0, 0, 20, 10), &signatures());
+        assert_eq!(reading.scene, Scene::EndScreen);
+        assert_eq!(reading.confidences, [0.0, 1.0, 0.0, 0.0, 0.0]);
+    }
+}
@@ -0,0 +1,222 @@
+//! renders fake super auto pets screenshots at arbitrary resolutions
+//! so that the extraction pipeline can be tested without real fixtures
+
+use image::{DynamicImage, RgbaImage};
+
+use crate::*;
+
+pub const BACKGROUND: [u8; 4] = [40, 120, 200, 255];
+pub const TURN_BOX_BACKGROUND: [u8; 4] = [30, 30, 30, 255];
+pub const BANDAGE: [u8; 4] = [240, 220, 200, 255];
+pub const ROLL_BUTTON: [u8; 4] = [80, 180, 60, 255];
+pub const END_TURN_BUTTON: [u8; 4] = [245, 150, 30, 255];
+pub const BATTLE_BUTTON: [u8; 4] = [240, 240, 240, 255];
+pub const MENU_LOGO: [u8; 4] = [250, 210, 60, 255];
+
+pub struct SynthScreenshot {
+    pub width: u32,
+    pub height: u32,
+    /// number of solid black columns on the left/right of the game viewport
+    pub margin_left: u32,
+    pub margin_right: u32,
+    pub num_hearts: usize,
+    /// horizontal distance between two neighbouring hearts, as a fraction of the viewport width
+    pub heart_spacing: f64,
+    /// hearts drawn after the full ones in the lost this turn color, then in the empty color
+    pub lost_hearts: usize,
+    pub empty_hearts: usize,
+    pub red: [u8; 4],
+    pub has_bandage: bool,
+    pub turn_number: u8,
+    pub digit_color: [u8; 4],
+    pub turn_box_color: [u8; 4],
+    /// which banner to draw, if any
+    pub outcome: Outcome,
+    /// whose scene markers to draw, if any
+    pub scene: Option<Scene>,
+    /// (level, filled experience pips) to draw for each team slot, left to right
+    pub team_levels: Vec<(u8, u8)>,
+    /// where to draw everything
+    pub layout: LayoutProfile,
+}
+
+impl SynthScreenshot {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            margin_left: 0,
+            margin_right: 0,
+            num_hearts: 3,
+            heart_spacing: 184.0 / REFERENCE_VIEWPORT.0 as f64,
+            lost_hearts: 0,
+            empty_hearts: 0,
+            red: [253, 6, 6, 255],
+            has_bandage: false,
+            turn_number: 12,
+            digit_color: WHITE,
+            turn_box_color: TURN_BOX_BACKGROUND,
+            outcome: Outcome::Unknown,
+            scene: None,
+            team_levels: vec![],
+            layout: LayoutProfile::default(),
+        }
+    }
+
+    pub fn render(&self) -> DynamicImage {
+        let mut img = RgbaImage::from_pixel(self.width, self.height, image::Rgba(BLACK));
+        let viewport_x = self.margin_left;
+        let viewport_width = self.width - self.margin_left - self.margin_right;
+        let viewport_height = self.height;
+        let mut viewport = Canvas { img: &mut img, x: viewport_x, width: viewport_width, height: viewport_height };
+        viewport.fill(&NormRect { x: 0.0, y: 0.0, width: 1.0, height: 1.0 }, BACKGROUND);
+
+        let layout = &self.layout;
+        let hearts = std::iter::repeat_n(self.red, self.num_hearts)
+            .chain(std::iter::repeat_n(layout.lost_heart_colors[0], self.lost_hearts))
+            .chain(std::iter::repeat_n(layout.empty_heart_colors[0], self.empty_hearts));
+        for (i, color) in hearts.enumerate() {
+            // hearts are 120px squares, 4px into the heart row of the reference viewport
+            let first_heart = NormRect::from_reference(4, 4, 120, 120);
+            let x = layout.heart_view.x + first_heart.x + self.heart_spacing * i as f64;
+            viewport.fill(&NormRect { x, y: layout.heart_view.y + first_heart.y, ..first_heart }, color);
+        }
+        if self.has_bandage {
+            viewport.fill(&around(bandage_point(layout), 15, 30), BANDAGE);
+        }
+
+        if let Some(banner) = layout.outcome_colors.iter().find(|c| c.outcome == self.outcome) {
+            viewport.fill(&layout.outcome_banner, banner.color);
+        }
+        for (rect, color) in self.scene.map_or(vec![], scene_elements) {
+            viewport.fill(&rect, color);
+        }
+        for (view, (level, experience)) in layout.level_views.iter().zip(self.team_levels.iter()) {
+            viewport.draw_number(&view.level, *level as u32);
+            let pips = level::pips_at_level(*level);
+            for pip in 0..pips {
+                // pips are 3/4 of their share of the bar, so neighbours do not touch
+                let share = view.experience.width / pips as f64;
+                let rect = NormRect { x: view.experience.x + share * pip as f64, width: share * 0.75, ..view.experience };
+                let colors = &layout.pip_colors;
+                viewport.fill(&rect, if pip < *experience { colors.filled } else { colors.empty });
+            }
+        }
+
+
+        let turn_box = layout.turn_number_view;
+        viewport.fill(&turn_box, self.turn_box_color);
+        let tens = (self.turn_number / 10) as usize;
+        let ones = (self.turn_number % 10) as usize;
+        let digit_rect = |offset: f64| NormRect {
+            x: turn_box.x + turn_box.width * offset / 66.0,
+            width: turn_box.width * 28.0 / 66.0,
+            ..turn_box
+        };
+        viewport.draw_digit(&digit_rect(3.0), tens, self.digit_color);
+        viewport.draw_digit(&digit_rect(35.0), ones, self.digit_color);
+
+        DynamicImage::ImageRgba8(img)
+    }
+}
+
+/// a square of `size` reference pixels whose top left corner is `offset`
+/// reference pixels up and to the left of `point`
+/// flat colored stand-ins for the UI elements only one screen of the game shows.
+/// they are made up, and independent of the `scene_signatures` of any profile
+fn scene_elements(scene: Scene) -> Vec<(NormRect, [u8; 4])> {
+    let rect = NormRect::from_reference;
+    match scene {
+        Scene::Shop => vec![(rect(40, 950, 250, 100), ROLL_BUTTON), (rect(1980, 950, 250, 100), END_TURN_BUTTON)],
+        Scene::Battle => vec![(rect(40, 950, 110, 100), BATTLE_BUTTON), (rect(180, 950, 110, 100), BATTLE_BUTTON)],
+        Scene::MainMenu => vec![(rect(835, 120, 600, 220), MENU_LOGO), (rect(835, 700, 600, 160), END_TURN_BUTTON)],
+        // the end screen is its outcome banner, see `outcome`
+        Scene::EndScreen | Scene::Unrelated => vec![],
+    }
+}
+
+/// the middle of the bandage drawn over the first heart
+pub fn bandage_point(layout: &LayoutProfile) -> NormPoint {
+    let offset = NormPoint::from_reference(75, 60);
+    NormPoint { x: layout.heart_view.x + offset.x, y: layout.heart_view.y + offset.y }
+}
+
+fn around(point: NormPoint, offset: u32, size: u32) -> NormRect {
+    let offset = NormRect::from_reference(offset, offset, size, size);
+    NormRect { x: point.x - offset.x, y: point.y - offset.y, width: offset.width, height: offset.height }
+}
+
+struct Canvas<'a> {
+    img: &'a mut RgbaImage,
+    x: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Canvas<'_> {
+    fn fill(&mut self, rect: &NormRect, color: [u8; 4]) {
+        let (x, y, w, h) = rect.to_pixels(self.width, self.height);
+        for py in y..y + h {
+            for px in x..x + w {
+                self.img.put_pixel(self.x + px, py, image::Rgba(color));
+            }
+        }
+    }
+
+    /// draw a number in the turn counter font on a turn counter colored box,
+    /// spaced the same way the two digits of the turn counter are
+    fn draw_number(&mut self, rect: &NormRect, value: u32) {
+        self.fill(rect, TURN_BOX_BACKGROUND);
+        let digits: Vec<usize> = value.to_string().bytes().map(|b| (b - b'0') as usize).collect();
+        let units = (32 * digits.len() + 2) as f64;
+        for (i, digit) in digits.into_iter().enumerate() {
+            let glyph = NormRect {
+                x: rect.x + rect.width * (3 + 32 * i) as f64 / units,
+                width: rect.width * 28.0 / units,
+                ..*rect
+            };
+            self.draw_digit(&glyph, digit, WHITE);
+        }
+    }
+
+    /// draw a digit by stretching its 16x16 template over the given rect
+    fn draw_digit(&mut self, rect: &NormRect, digit: usize, color: [u8; 4]) {
+        let template = [VEC_0, VEC_1, VEC_2, VEC_3, VEC_4, VEC_5, VEC_6, VEC_7, VEC_8, VEC_9][digit];
+        self.draw_pattern(rect, &template, color);
+    }
+
+    /// stretch the set pixels of a 16x16 pattern over the given rect
+    fn draw_pattern(&mut self, rect: &NormRect, template: &[u8; 256], color: [u8; 4]) {
+        let (x, y, w, h) = rect.to_pixels(self.width, self.height);
+        for py in 0..h {
+            for px in 0..w {
+                let tx = (px * 16 / w) as usize;
+                let ty = (py * 16 / h) as usize;
+                if template[ty * 16 + tx] == 1 {
+                    self.img.put_pixel(self.x + x + px, y + py, image::Rgba(color));
+                }
+            }
+        }
+    }
+}
+
+/// a dark image with `value` drawn in the turn counter font, each digit
+/// `glyph_width`x`glyph_height` pixels with `gap` pixels around and between them
+pub fn render_number(value: u32, glyph_width: u32, glyph_height: u32, gap: u32) -> DynamicImage {
+    let digits: Vec<usize> = value.to_string().bytes().map(|b| (b - b'0') as usize).collect();
+    let width = gap + digits.len() as u32 * (glyph_width + gap);
+    let height = glyph_height + gap * 2;
+    let mut img = RgbaImage::from_pixel(width, height, image::Rgba(TURN_BOX_BACKGROUND));
+    let mut canvas = Canvas { img: &mut img, x: 0, width, height };
+    for (i, digit) in digits.into_iter().enumerate() {
+        let x = gap + i as u32 * (glyph_width + gap);
+        let rect = NormRect {
+            x: x as f64 / width as f64,
+            y: gap as f64 / height as f64,
+            width: glyph_width as f64 / width as f64,
+            height: glyph_height as f64 / height as f64,
+        };
+        canvas.draw_digit(&rect, digit, WHITE);
+    }
+    DynamicImage::ImageRgba8(img)
+}
@@ -0,0 +1,124 @@
+//! turn a region of a screenshot into a black and white mask of its bright
+//! foreground (eg: the white digits of the turn counter). the threshold is
+//! picked per region with otsu's method, so recompressed, colour managed or
+//! slightly tinted screenshots still binarize cleanly
+
+use image::{DynamicImage, GenericImageView, RgbaImage, SubImage};
+
+use crate::{BLACK, WHITE};
+
+/// regions whose darkest and brightest pixels are closer than this
+/// have no foreground at all, otsu would just split the noise
+const MIN_CONTRAST: u8 = 64;
+
+/// the foreground (digits) never covers more than this fraction of the region.
+/// if it does, we split the background instead of the digits from the background
+const MAX_FOREGROUND_FRACTION: f64 = 0.5;
+
+/// perceived brightness of a pixel (ITU-R BT.601)
+pub fn luma(px: [u8; 4]) -> u8 {
+    let [r, g, b, _] = px;
+    ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8
+}
+
+/// otsu's method: the threshold that maximizes the variance between the
+/// pixels at or below it and the pixels above it
+pub fn otsu_threshold(histogram: &[u32; 256]) -> u8 {
+    let total: u64 = histogram.iter().map(|c| *c as u64).sum();
+    let sum_all: u64 = histogram.iter().enumerate().map(|(i, c)| i as u64 * *c as u64).sum();
+    let mut best_threshold = 0;
+    let mut best_variance = 0.0;
+    let mut weight_below = 0u64;
+    let mut sum_below = 0u64;
+    for (t, count) in histogram.iter().enumerate() {
+        weight_below += *count as u64;
+        sum_below += t as u64 * *count as u64;
+        let weight_above = total - weight_below;
+        if weight_below == 0 || weight_above == 0 {
+            continue;
+        }
+        let mean_below = sum_below as f64 / weight_below as f64;
+        let mean_above = (sum_all - sum_below) as f64 / weight_above as f64;
+        let variance = weight_below as f64 * weight_above as f64 * (mean_below - mean_above).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best_threshold = t as u8;
+        }
+    }
+    best_threshold
+}
+
+/// pick the brightness above which a pixel of the view is foreground.
+/// `None` if the view is too flat to contain any foreground
+pub fn foreground_threshold(view: &SubImage<&DynamicImage>) -> Option<u8> {
+    let lumas: Vec<u8> = view.pixels().map(|(_, _, px)| luma(px.0)).collect();
+    let darkest = *lumas.iter().min()?;
+    let brightest = *lumas.iter().max()?;
+    if brightest - darkest < MIN_CONTRAST {
+        return None;
+    }
+    let mut floor = 0;
+    let mut threshold = 0;
+    // with more than two tones (eg: dark outlines around the digits on a lighter
+    // background) the first split can land below the background. keep splitting
+    // the brighter side until the foreground is small enough to be the digits
+    for _ in 0..3 {
+        let mut histogram = [0u32; 256];
+        lumas.iter().filter(|l| **l >= floor).for_each(|l| histogram[*l as usize] += 1);
+        threshold = otsu_threshold(&histogram);
+        let foreground = lumas.iter().filter(|l| **l > threshold).count();
+        if (foreground as f64) <= lumas.len() as f64 * MAX_FOREGROUND_FRACTION {
+            break;
+        }
+        floor = threshold.saturating_add(1);
+    }
+    Some(threshold)
+}
+
+/// a copy of the view where foreground pixels are `WHITE` and everything else is `BLACK`
+pub fn binarize(view: &SubImage<&DynamicImage>) -> DynamicImage {
+    let (width, height) = view.dimensions();
+    let threshold = foreground_threshold(view);
+    let mask = RgbaImage::from_fn(width, height, |x, y| {
+        let is_foreground = threshold.is_some_and(|t| luma(view.get_pixel(x, y).0) > t);
+        image::Rgba(if is_foreground { WHITE } else { BLACK })
+    });
+    DynamicImage::ImageRgba8(mask)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn otsu_splits_two_tones() {
+        let mut histogram = [0; 256];
+        histogram[40] = 100;
+        histogram[230] = 20;
+        let t = otsu_threshold(&histogram);
+        assert!((40..230).contains(&t));
+    }
+
+    #[test]
+    fn binarize_keeps_only_the_brightest_tone() {
+        // dark outline, mid tone background, and a few near-white digit pixels
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_fn(20, 10, |x, _| {
+            image::Rgba(match x {
+                0..=3 => [20, 20, 20, 255],
+                4..=16 => [120, 90, 60, 255],
+                _ => [238, 244, 230, 255],
+            })
+        }));
+        let mask = binarize(&img.view(0, 0, 20, 10));
+        assert_eq!(mask.get_pixel(0, 0).0, BLACK);
+        assert_eq!(mask.get_pixel(10, 0).0, BLACK);
+        assert_eq!(mask.get_pixel(18, 0).0, WHITE);
+    }
+
+    #[test]
+    fn flat_regions_have_no_foreground() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(8, 8, image::Rgba([200, 200, 200, 255])));
+        assert_eq!(foreground_threshold(&img.view(0, 0, 8, 8)), None);
+        assert!(binarize(&img.view(0, 0, 8, 8)).pixels().all(|(_, _, px)| px.0 == BLACK));
+    }
+}
@@ -0,0 +1,297 @@
+//! regenerates the digit templates in `wasm/src/digit_templates.rs` from labelled screenshots.
+//!
+//! usage: template-gen <screenshot dir> <labels.json> [output file]
+//!        template-gen decorations <screenshot dir> <labels.json> [output file]
+//!
+//! `labels.json` maps screenshot file names to the turn number shown on them:
+//! `{ "Screenshot_20240714-090957.png": 17 }`.
+//! every screenshot is run through the same digit clipping the wasm module uses
+//! (`PetsImage::get_digits_vectors`). two things are generated from the crops:
+//! - `VEC_n`: a pixel of a digit's template is set if the majority of the samples
+//!   of that digit have it set
+//! - `DIGIT_SAMPLES`: every distinct crop, plus copies of it shifted by one pixel in
+//!   each direction, which the nearest neighbour classifier compares against
+//!
+//! digits that have no samples keep their current template, and use it as their
+//! only sample, without shifts: no crops are made up for a digit nobody labelled. the source is printed to stdout unless an output file is given.
+//!
+//! with `decorations`, `labels.json` maps screenshot file names to the icon drawn
+//! over each heart slot, left to right (eg: `"bandage"`), or `"plain"`:
+//! `{ "Screenshot_20240714-090957.png": ["bandage", "plain", "plain"] }`,
+//! and the crop of every labelled slot is written to `wasm/src/heart_decorations.rs`.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const USAGE: &str = "usage: template-gen [decorations] <screenshot dir> <labels.json> [output file]";
+
+const HEADER: &str = "\
+// @generated by template-gen from labelled turn counter crops. do not edit by hand,
+// see wasm/template-gen for how to regenerate it.
+//
+// binarized 16x16 template of each digit of the turn counter font, row by row.
+
+";
+
+const HEART_DECORATIONS_HEADER: &str = "\
+// @generated by template-gen from labelled heart slot crops. do not edit by hand,
+// see wasm/template-gen for how to regenerate it.
+//
+// scaled down rgb crop of each heart slot, labelled with the icon drawn over it
+// (or `plain`), see `icon::icon_vector`.
+
+use crate::icon::IconVector;
+
+";
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.split_first() {
+        Some((mode, rest)) if mode == "decorations" => {
+            let decorations = collect_slot_icons(rest, |img| img.get_heart_slot_vectors())?;
+            eprintln!("{} heart slot crops", decorations.len());
+            write_source(render_named_icons(HEART_DECORATIONS_HEADER, "HEART_DECORATIONS", &decorations), rest.get(2))
+        }
+        _ => run_digits(&args),
+    }
+}
+
+/// (screenshot dir, labels file, optional output file)
+fn parse_paths(args: &[String]) -> Result<(&String, &String, Option<&String>), String> {
+    match args {
+        [dir, labels] => Ok((dir, labels, None)),
+        [dir, labels, output] => Ok((dir, labels, Some(output))),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn write_source(source: String, output: Option<&String>) -> Result<(), String> {
+    match output {
+        Some(output) => std::fs::write(output, source).map_err(|e| format!("failed to write {output}: {e}")),
+        None => {
+            print!("{source}");
+            Ok(())
+        }
+    }
+}
+
+fn run_digits(args: &[String]) -> Result<(), String> {
+    let (dir, labels_path, output) = parse_paths(args)?;
+    let labels = std::fs::read(labels_path).map_err(|e| format!("failed to read {labels_path}: {e}"))?;
+    let labels: BTreeMap<String, u8> = serde_json::from_slice(&labels)
+        .map_err(|e| format!("{labels_path} must map file names to turn numbers: {e}"))?;
+
+    let mut samples: [Vec<[u8; 256]>; 10] = Default::default();
+    for (file, turn_number) in labels {
+        if !(10..=99).contains(&turn_number) {
+            eprintln!("skipping {file}: only two digit turn numbers can be split into digits");
+            continue;
+        }
+        let path = Path::new(dir).join(&file);
+        let bytes = std::fs::read(&path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        match wasm::get_pets_img(&bytes, |img| img.get_digits_vectors()) {
+            Ok(Some((tens, ones))) => {
+                samples[(turn_number / 10) as usize].push(tens);
+                samples[(turn_number % 10) as usize].push(ones);
+            }
+            Ok(None) => eprintln!("skipping {file}: {}", wasm::ExtractError::DigitSegmentation),
+            Err(e) => eprintln!("skipping {file}: {e}"),
+        }
+    }
+
+    let labelled = samples.each_ref().map(|s| s.len());
+    let mut templates = current_templates();
+    for (digit, digit_samples) in samples.iter_mut().enumerate() {
+        if digit_samples.is_empty() {
+            eprintln!("no samples of digit {digit}, keeping its current template");
+            digit_samples.push(templates[digit]);
+            continue;
+        }
+        eprintln!("digit {digit}: {} samples", digit_samples.len());
+        templates[digit] = vote(digit_samples);
+    }
+
+    write_source(render_templates(&templates, &reference_samples(&samples, &labelled), &labelled), output)
+}
+
+fn current_templates() -> [[u8; 256]; 10] {
+    [
+        wasm::VEC_0,
+        wasm::VEC_1,
+        wasm::VEC_2,
+        wasm::VEC_3,
+        wasm::VEC_4,
+        wasm::VEC_5,
+        wasm::VEC_6,
+        wasm::VEC_7,
+        wasm::VEC_8,
+        wasm::VEC_9,
+    ]
+}
+
+/// a pixel is set if it is set in more than half of the samples
+fn vote(samples: &[[u8; 256]]) -> [u8; 256] {
+    let mut out = [0; 256];
+    for (i, px) in out.iter_mut().enumerate() {
+        let set = samples.iter().filter(|s| s[i] == 1).count();
+        if set * 2 > samples.len() {
+            *px = 1;
+        }
+    }
+    out
+}
+
+/// move every set pixel by (dx, dy), dropping whatever falls off the edge
+fn shift(sample: &[u8; 256], dx: i32, dy: i32) -> [u8; 256] {
+    let mut out = [0; 256];
+    for y in 0..16 {
+        for x in 0..16 {
+            let (from_x, from_y) = (x - dx, y - dy);
+            if (0..16).contains(&from_x) && (0..16).contains(&from_y) {
+                out[(y * 16 + x) as usize] = sample[(from_y * 16 + from_x) as usize];
+            }
+        }
+    }
+    out
+}
+
+/// every distinct sample of each digit along with its one pixel shifts. digits
+/// without any labelled crops (see `labelled`) only have their template, unshifted
+fn reference_samples(samples: &[Vec<[u8; 256]>; 10], labelled: &[usize; 10]) -> Vec<(u8, [u8; 256])> {
+    let mut out: Vec<(u8, [u8; 256])> = vec![];
+    for (digit, digit_samples) in samples.iter().enumerate() {
+        let shifts: &[(i32, i32)] = if labelled[digit] == 0 { &[(0, 0)] } else { &[(0, 0), (-1, 0), (1, 0), (0, -1), (0, 1)] };
+        for sample in digit_samples {
+            for (dx, dy) in shifts.iter().copied() {
+                let reference = (digit as u8, shift(sample, dx, dy));
+                if !out.contains(&reference) {
+                    out.push(reference);
+                }
+            }
+        }
+    }
+    out
+}
+
+fn render_vector(vector: &[u8; 256]) -> String {
+    let values: Vec<String> = vector.iter().map(|v| v.to_string()).collect();
+    format!("[{}]", values.join(", "))
+}
+
+/// `labelled` is how many labelled crops of each digit went into the samples. it is
+/// written into the source, so a digit that only has its template is easy to spot
+fn render_templates(templates: &[[u8; 256]; 10], samples: &[(u8, [u8; 256])], labelled: &[usize; 10]) -> String {
+    let mut out = HEADER.to_string();
+    for (digit, template) in templates.iter().enumerate() {
+        out.push_str(&format!("pub const VEC_{digit}: [u8; 256] = {};\n", render_vector(template)));
+    }
+    out.push_str("\n// every reference sample of each digit, compared against by `get_digit_similarity`.\n");
+    out.push_str(&format!("// labelled crops of each digit, 0 to 9: {labelled:?}. a digit without any is only\n"));
+    out.push_str("// compared against its template, so regenerate it from more screenshots\n");
+    out.push_str("pub const DIGIT_SAMPLES: &[(u8, [u8; 256])] = &[\n");
+    for (digit, sample) in samples {
+        out.push_str(&format!("    ({digit}, {}),\n", render_vector(sample)));
+    }
+    out.push_str("];\n");
+    out
+}
+
+/// the distinct (name, icon) of every labelled slot. the labels map file
+/// names to the name of what is in each slot, left to right, `null` for nothing
+fn collect_slot_icons(
+    args: &[String],
+    extract: impl Fn(wasm::PetsImage) -> Vec<wasm::icon::IconVector>,
+) -> Result<Vec<(String, wasm::icon::IconVector)>, String> {
+    let (dir, labels_path, _) = parse_paths(args)?;
+    let labels = std::fs::read(labels_path).map_err(|e| format!("failed to read {labels_path}: {e}"))?;
+    let labels: BTreeMap<String, Vec<Option<String>>> = serde_json::from_slice(&labels)
+        .map_err(|e| format!("{labels_path} must map file names to lists of names: {e}"))?;
+
+    let mut icons: Vec<(String, wasm::icon::IconVector)> = vec![];
+    for (file, slots) in labels {
+        let path = Path::new(dir).join(&file);
+        let bytes = std::fs::read(&path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        let vectors = match wasm::get_pets_img(&bytes, &extract) {
+            Ok(vectors) => vectors,
+            Err(e) => {
+                eprintln!("skipping {file}: {e}");
+                continue;
+            }
+        };
+        if vectors.len() != slots.len() {
+            eprintln!("skipping {file}: labelled {} slots but the layout has {}", slots.len(), vectors.len());
+            continue;
+        }
+        for (name, vector) in slots.into_iter().zip(vectors) {
+            let Some(name) = name else { continue };
+            if !icons.contains(&(name.clone(), vector)) {
+                icons.push((name, vector));
+            }
+        }
+    }
+    Ok(icons)
+}
+
+/// render a generated file holding `const_name: &[(&str, IconVector)]`
+fn render_named_icons(header: &str, const_name: &str, icons: &[(String, wasm::icon::IconVector)]) -> String {
+    let mut out = header.to_string();
+    out.push_str(&format!("pub const {const_name}: &[(&str, IconVector)] = &[\n"));
+    for (name, icon) in icons {
+        let values: Vec<String> = icon.iter().map(|v| v.to_string()).collect();
+        out.push_str(&format!("    ({name:?}, [{}]),\n", values.join(", ")));
+    }
+    out.push_str("];\n");
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn regenerating_without_labels_is_a_noop() {
+        // with no labelled crops, every digit falls back to its template as its only sample
+        let samples = current_templates().map(|template| vec![template]);
+        let source = render_templates(&current_templates(), &reference_samples(&samples, &[0; 10]), &[0; 10]);
+        assert_eq!(source, include_str!("../../src/digit_templates.rs"));
+    }
+
+    #[test]
+    fn renders_named_icons() {
+        // the checked in files were rendered with the same headers
+        assert!(include_str!("../../src/heart_decorations.rs").starts_with(HEART_DECORATIONS_HEADER));
+        assert!(render_named_icons(HEART_DECORATIONS_HEADER, "HEART_DECORATIONS", &[]).ends_with("pub const HEART_DECORATIONS: &[(&str, IconVector)] = &[\n];\n"));
+        let source = render_named_icons(HEART_DECORATIONS_HEADER, "HEART_DECORATIONS", &[("bandage".to_string(), [3; 768])]);
+        assert!(source.contains("    (\"bandage\", [3, 3, "));
+    }
+
+    #[test]
+    fn shifting_moves_pixels() {
+        let mut sample = [0; 256];
+        sample[0] = 1;
+        let shifted = shift(&sample, 1, 1);
+        assert_eq!(shifted[17], 1);
+        assert_eq!(shifted.iter().filter(|px| **px == 1).count(), 1);
+        assert!(shift(&sample, -1, 0).iter().all(|px| *px == 0));
+    }
+
+    #[test]
+    fn majority_vote_per_pixel() {
+        let mut a = [0; 256];
+        let mut b = [0; 256];
+        let c = [0; 256];
+        a[0] = 1;
+        b[0] = 1;
+        a[1] = 1;
+        let template = vote(&[a, b, c]);
+        assert_eq!(template[0], 1);
+        assert_eq!(template[1], 0);
+    }
+}